@@ -1,51 +1,32 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use rust_santorini::{Game, Status, TowerStates};
+use rust_santorini::{bitboard::BitBoard, Game};
 
-const TSE: TowerStates = TowerStates::Empty;
-const TS2: TowerStates = TowerStates::Level2;
 fn criterion_benchmark(c: &mut Criterion) {
-    let game = Game {
-        board: [[TowerStates::Empty; 5]; 5],
-        player_locations: [((1, 1), (3, 3)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-        player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-    };
+    let game =
+        Game::from_position_string("00000/00000/00000/00000/00000 B2,D4/-,-/-,- PDD 0").unwrap();
     c.bench_function("list_possible_actions_on_empty", |b| {
         b.iter(|| black_box(game).list_possible_actions(0))
     });
-    let game = Game {
-        board: [[TowerStates::Empty; 5]; 5],
-        player_locations: [((0, 0), (4, 4)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-        player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-    };
+    let bitboard = BitBoard::from(game);
+    c.bench_function("bitboard_list_possible_actions_on_empty", |b| {
+        b.iter(|| black_box(bitboard).list_possible_actions(0))
+    });
+    c.bench_function("possible_actions_on_empty", |b| {
+        b.iter(|| black_box(game).possible_actions(0).count())
+    });
+    let game =
+        Game::from_position_string("00000/00000/00000/00000/00000 A1,E5/-,-/-,- PDD 0").unwrap();
     c.bench_function("list_possible_actions_on_empty_near_edge", |b| {
         b.iter(|| black_box(game).list_possible_actions(0))
     });
 
-    let game = Game {
-        board: [
-            [TS2; 5],
-            [TS2; 5],
-            [TS2, TS2, TSE, TSE, TS2],
-            [TS2; 5],
-            [TS2; 5],
-        ],
-        player_locations: [((2, 2), (2, 3)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-        player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-    };
+    let game =
+        Game::from_position_string("22222/22222/22002/22222/22222 C3,D3/-,-/-,- PDD 0").unwrap();
     c.bench_function("list_possible_actions_where_trapped", |b| {
         b.iter(|| black_box(game).list_possible_actions(0))
     });
-    let game = Game {
-        board: [
-            [TS2; 5],
-            [TS2; 5],
-            [TS2, TS2, TSE, TSE, TSE],
-            [TS2; 5],
-            [TS2; 5],
-        ],
-        player_locations: [((2, 2), (2, 3)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-        player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-    };
+    let game =
+        Game::from_position_string("22222/22222/22000/22222/22222 C3,D3/-,-/-,- PDD 0").unwrap();
     c.bench_function("list_possible_actions_where_only_one_option", |b| {
         b.iter(|| black_box(game).list_possible_actions(0))
     });
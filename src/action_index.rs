@@ -0,0 +1,116 @@
+//! Actions as a number below `ACTION_COUNT`: the worker, the direction it moves in and the
+//! direction it builds in from where it ends up, so a policy can give each one a score.
+
+use crate::*;
+
+pub const ACTION_COUNT: usize = 2 * 8 * 8;
+
+const DIRECTIONS: [(i8, i8); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+fn direction(from: (u8, u8), to: (u8, u8)) -> Option<usize> {
+    let step = (to.0 as i8 - from.0 as i8, to.1 as i8 - from.1 as i8);
+    DIRECTIONS.iter().position(|&direction| direction == step)
+}
+
+fn step(size: BoardSize, from: (u8, u8), direction: usize) -> Option<(u8, u8)> {
+    let (dx, dy) = DIRECTIONS[direction];
+    let square = ((from.0 as i8 + dx) as u8, (from.1 as i8 + dy) as u8);
+    if size.contains(from) && size.contains(square) {
+        Some(square)
+    } else {
+        None
+    }
+}
+
+impl Action {
+    /// `None` for actions that don't fit in a single step and build: Artemis moving twice, a
+    /// second build or a dome from Atlas
+    pub fn to_index(&self, game: &Game, player_id: usize) -> Option<usize> {
+        if self.extra_build.is_some() || self.dome {
+            return None;
+        }
+        let movement = direction(game.worker_location(player_id, self.worker), self.movement)?;
+        let build = direction(self.movement, self.build)?;
+        Some(self.worker as usize * 64 + movement * 8 + build)
+    }
+
+    /// The action with the given index, or `None` if it would leave the board. The action
+    /// isn't checked to be legal.
+    pub fn from_index(game: &Game, player_id: usize, index: usize) -> Option<Self> {
+        if index >= ACTION_COUNT {
+            return None;
+        }
+        let worker = if index < 64 { Worker::One } else { Worker::Two };
+        let movement = step(
+            game.size,
+            game.worker_location(player_id, worker),
+            index / 8 % 8,
+        )?;
+        let build = step(game.size, movement, index % 8)?;
+        Some(Action::new(worker, movement, build))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legal_actions_round_trip() {
+        let player = random_choice_player::RandomChoice::new();
+        for _ in 0..20 {
+            let record = main_loop(
+                [Some(&player), Some(&player), None, None],
+                [None; MAX_PLAYERS],
+                &mut [],
+            );
+            for (_, player_id, game, action) in record.training_data().unwrap() {
+                let index = action.to_index(&game, player_id).unwrap();
+                assert!(index < ACTION_COUNT);
+                assert_eq!(Action::from_index(&game, player_id, index), Some(action));
+            }
+        }
+    }
+
+    #[test]
+    fn indices_round_trip() {
+        let game = Game::from_position_string("00000/00000/00000/00000/00000 A1,C3/-,-/-,- PDD 0")
+            .unwrap();
+        let actions: Vec<Action> = (0..ACTION_COUNT)
+            .filter_map(|index| {
+                let action = Action::from_index(&game, 0, index)?;
+                assert_eq!(action.to_index(&game, 0), Some(index));
+                Some(action)
+            })
+            .collect();
+        // Builds from every square next to the worker in the corner, and the one in the middle
+        assert_eq!(actions.len(), (5 + 5 + 8) + 8 * 8);
+        let dome = Action {
+            dome: true,
+            ..actions[0]
+        };
+        assert_eq!(dome.to_index(&game, 0), None);
+    }
+
+    #[test]
+    fn indices_stay_on_small_boards() {
+        let game = Game::from_position_string("000/000/000 A1,C3/-,-/-,- PDD 0").unwrap();
+        let actions: Vec<Action> = (0..ACTION_COUNT)
+            .filter_map(|index| Action::from_index(&game, 0, index))
+            .collect();
+        // Both workers are in a corner, with 3 squares to move to and 5 + 5 + 8 to build on
+        assert_eq!(actions.len(), 2 * (5 + 5 + 8));
+        for action in actions {
+            assert!(game.size.contains(action.movement) && game.size.contains(action.build));
+        }
+    }
+}
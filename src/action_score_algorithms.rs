@@ -94,7 +94,7 @@ impl ActionScorer for PrioritizeBlocking {
                 (b.0 + 1, b.1),
                 (b.0 + 1, b.1 + 1),
             ] {
-                if s.0 <= 4 && s.1 <= 4 && s != w1 && s != w2 {
+                if game.size.contains(s) && s != w1 && s != w2 {
                     // Don't include the square players workers are on
                     max_near_height =
                         max_near_height.max(game.board[s.0 as usize][s.1 as usize].to_int());
@@ -151,60 +151,54 @@ impl ActionScorer for PrioritizeNextToPlayer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Game, Status, TowerStates};
-    const TSE: TowerStates = TowerStates::Empty;
-    const TS1: TowerStates = TowerStates::Level1;
+    use crate::Game;
 
     #[test]
     fn prioritize_climbing_scores_climb_highest() {
-        let game = Game {
-            board: [
-                [TSE, TS1, TSE, TSE, TSE],
-                [TSE; 5],
-                [TSE; 5],
-                [TSE; 5],
-                [TSE; 5],
-            ],
-            player_locations: [((0, 0), (3, 3)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-
-            player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-        };
+        let game = Game::from_position_string("01000/00000/00000/00000/00000 A1,D4/-,-/-,- PDD 0")
+            .unwrap();
         let climbing = PrioritizeClimbing {};
         let actions = game.list_possible_actions(0);
-        let mut max = ((Worker::One, (0, 0), (0, 0)), f32::MIN);
+        let mut max = (Action::new(Worker::One, (0, 0), (0, 0)), f32::MIN);
         for action in actions.iter() {
-            let score =
-                climbing.get_score(&game, 0, action.0, action.1, action.2, false, false, false);
+            let score = climbing.get_score(
+                &game,
+                0,
+                action.worker,
+                action.movement,
+                action.build,
+                false,
+                false,
+                false,
+            );
             if score > max.1 {
                 max = (*action, score);
             }
         }
-        assert_eq!((max.0).1, (0, 1));
+        assert_eq!(max.0.movement, (0, 1));
     }
     #[test]
     fn prioritize_climbing_scores_drop_lowest() {
-        let game = Game {
-            board: [
-                [TS1, TSE, TS1, TS1, TS1],
-                [TS1; 5],
-                [TS1; 5],
-                [TS1; 5],
-                [TS1; 5],
-            ],
-            player_locations: [((0, 0), (3, 3)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-
-            player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-        };
+        let game = Game::from_position_string("10111/11111/11111/11111/11111 A1,D4/-,-/-,- PDD 0")
+            .unwrap();
         let climbing = PrioritizeClimbing {};
         let actions = game.list_possible_actions(0);
-        let mut max = ((Worker::One, (0, 0), (0, 0)), f32::MAX);
+        let mut max = (Action::new(Worker::One, (0, 0), (0, 0)), f32::MAX);
         for action in actions.iter() {
-            let score =
-                climbing.get_score(&game, 0, action.0, action.1, action.2, false, false, false);
+            let score = climbing.get_score(
+                &game,
+                0,
+                action.worker,
+                action.movement,
+                action.build,
+                false,
+                false,
+                false,
+            );
             if score < max.1 {
                 max = (*action, score);
             }
         }
-        assert_eq!((max.0).1, (0, 1));
+        assert_eq!(max.0.movement, (0, 1));
     }
 }
@@ -0,0 +1,714 @@
+use crate::*;
+
+pub const BOARD_MASK: u32 = (1 << 25) - 1;
+
+const fn neighbour_table() -> [u32; 25] {
+    let mut table = [0; 25];
+    let mut square = 0;
+    while square < 25 {
+        let (x, y) = ((square / 5) as i8, (square % 5) as i8);
+        let mut dx = -1;
+        while dx <= 1 {
+            let mut dy = -1;
+            while dy <= 1 {
+                let (nx, ny) = (x + dx, y + dy);
+                if (dx != 0 || dy != 0) && nx >= 0 && ny >= 0 && is_in_storage((nx as u8, ny as u8))
+                {
+                    table[square] |= 1 << (nx * 5 + ny);
+                }
+                dy += 1;
+            }
+            dx += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+pub const NEIGHBOURS: [u32; 25] = neighbour_table();
+
+/// The squares of a board of the given size
+pub fn board_mask(size: BoardSize) -> u32 {
+    if size == BoardSize::FULL {
+        return BOARD_MASK;
+    }
+    rectangle(size.width(), size.height())
+}
+
+/// The squares of the first `height` rows and `width` columns
+fn rectangle(width: u8, height: u8) -> u32 {
+    let row = (1 << width) - 1;
+    (0..height).fold(0, |mask, x| mask | row << (x * 5))
+}
+
+/// The size of the board with the squares in `mask`, which `board_mask` returned
+fn mask_size(mask: u32) -> BoardSize {
+    let width = (mask & 0b11111).count_ones() as u8;
+    BoardSize {
+        width,
+        height: mask.count_ones() as u8 / width,
+    }
+}
+
+/// Squares on the edge of a board of the given size
+fn perimeter(size: BoardSize) -> u32 {
+    let inner = rectangle(size.width() - 2, size.height() - 2);
+    board_mask(size) & !(inner << 6)
+}
+
+pub fn square_to_index((x, y): (u8, u8)) -> Option<u32> {
+    if is_in_storage((x, y)) {
+        Some(x as u32 * 5 + y as u32)
+    } else {
+        None
+    }
+}
+
+pub fn index_to_square(index: u32) -> (u8, u8) {
+    ((index / 5) as u8, (index % 5) as u8)
+}
+
+struct Bits(u32);
+
+impl Iterator for Bits {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> {
+        if self.0 == 0 {
+            None
+        } else {
+            let index = self.0.trailing_zeros();
+            self.0 &= self.0 - 1;
+            Some(index)
+        }
+    }
+}
+
+/// Bitboard form of a `Game`, with one bit per square in row-major order.
+/// Workers that aren't on the board have an empty mask, and come back as (17, 17).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BitBoard {
+    // levels[0] to levels[3] hold the squares at Level1, Level2, Level3 and Capped
+    pub levels: [u32; 4],
+    pub workers: [[u32; 2]; MAX_PLAYERS],
+    pub player_statuses: [Status; MAX_PLAYERS],
+    pub player_gods: [Option<God>; MAX_PLAYERS],
+    pub to_move: usize,
+    pub piece_supply: Option<[u8; 4]>,
+    pub teams: bool,
+    pub effects: TurnEffects,
+    /// The squares of the board, see `board_mask`
+    pub squares: u32,
+}
+
+impl From<Game> for BitBoard {
+    fn from(game: Game) -> Self {
+        let mut levels = [0; 4];
+        for (x, row) in game.board.iter().enumerate() {
+            for (y, tower) in row.iter().enumerate() {
+                let level = tower.to_int();
+                if level > 0 {
+                    levels[level as usize - 1] |= 1 << (x * 5 + y);
+                }
+            }
+        }
+        let mut workers = [[0; 2]; MAX_PLAYERS];
+        for (masks, &(w1, w2)) in workers.iter_mut().zip(game.player_locations.iter()) {
+            for (mask, &w) in masks.iter_mut().zip([w1, w2].iter()) {
+                *mask = square_to_index(w).map_or(0, |index| 1 << index);
+            }
+        }
+        Self {
+            levels,
+            workers,
+            player_statuses: game.player_statuses,
+            player_gods: game.player_gods,
+            to_move: game.to_move,
+            piece_supply: game.piece_supply,
+            teams: game.teams,
+            effects: game.effects,
+            squares: board_mask(game.size),
+        }
+    }
+}
+
+impl From<BitBoard> for Game {
+    fn from(bitboard: BitBoard) -> Self {
+        let mut board = [[TowerStates::Empty; 5]; 5];
+        for (level, &mask) in [
+            TowerStates::Level1,
+            TowerStates::Level2,
+            TowerStates::Level3,
+            TowerStates::Capped,
+        ]
+        .iter()
+        .zip(bitboard.levels.iter())
+        {
+            for index in Bits(mask) {
+                let (x, y) = index_to_square(index);
+                board[x as usize][y as usize] = *level;
+            }
+        }
+        let mut player_locations = [((17, 17), (17, 17)); MAX_PLAYERS];
+        for (location, masks) in player_locations.iter_mut().zip(bitboard.workers.iter()) {
+            let to_square = |mask: u32| {
+                if mask == 0 {
+                    (17, 17)
+                } else {
+                    index_to_square(mask.trailing_zeros())
+                }
+            };
+            *location = (to_square(masks[0]), to_square(masks[1]));
+        }
+        let mut game = Game::new(board, player_locations, bitboard.player_statuses);
+        game.player_gods = bitboard.player_gods;
+        game.to_move = bitboard.to_move;
+        game.piece_supply = bitboard.piece_supply;
+        game.teams = bitboard.teams;
+        game.effects = bitboard.effects;
+        game.size = mask_size(bitboard.squares);
+        game.refresh_hash();
+        game
+    }
+}
+
+impl BitBoard {
+    pub fn occupied(&self) -> u32 {
+        self.workers
+            .iter()
+            .zip(self.player_statuses.iter())
+            .filter(|(_, &status)| status == Status::Playing)
+            .fold(0, |occupied, (masks, _)| occupied | masks[0] | masks[1])
+    }
+
+    /// Workers of the player and, in team play, of their teammate
+    fn team_workers(&self, player_id: usize) -> u32 {
+        let mut workers = self.workers[player_id][0] | self.workers[player_id][1];
+        if self.teams {
+            let teammate = (player_id + 2) % MAX_PLAYERS;
+            if self.player_statuses[teammate] == Status::Playing {
+                workers |= self.workers[teammate][0] | self.workers[teammate][1];
+            }
+        }
+        workers
+    }
+
+    fn are_teammates(&self, player_id: usize, other: usize) -> bool {
+        on_same_team(self.teams, player_id, other)
+    }
+
+    /// See `Game::can_move_up`
+    fn can_move_up(&self, player_id: usize) -> bool {
+        match self.effects.moved_up {
+            Some(athena) => {
+                self.player_statuses[athena] == Status::Dead
+                    || self.are_teammates(athena, player_id)
+            }
+            None => true,
+        }
+    }
+
+    /// See `Game::opposed_by_hera`
+    fn opposed_by_hera(&self, player_id: usize) -> bool {
+        (0..MAX_PLAYERS).any(|other| {
+            self.player_gods[other] == Some(God::Hera)
+                && self.player_statuses[other] == Status::Playing
+                && !self.are_teammates(player_id, other)
+        })
+    }
+
+    /// Squares next to the workers of the player's teammate, which team play doesn't allow
+    /// domes on
+    fn teammate_neighbours(&self, player_id: usize) -> u32 {
+        let teammates = self.team_workers(player_id)
+            & !(self.workers[player_id][0] | self.workers[player_id][1]);
+        Bits(teammates).fold(0, |mask, index| mask | NEIGHBOURS[index as usize])
+    }
+
+    pub fn height(&self, index: u32) -> u8 {
+        let bit = 1 << index;
+        self.levels
+            .iter()
+            .position(|&mask| mask & bit != 0)
+            .map_or(0, |level| level as u8 + 1)
+    }
+
+    /// Squares with a tower no higher than `level`
+    fn at_most(&self, level: u8) -> u32 {
+        self.levels
+            .iter()
+            .skip(level as usize)
+            .fold(self.squares, |mask, &level_mask| mask & !level_mask)
+    }
+
+    fn push_square(&self, from: u32, to: u32, occupied: u32) -> Option<u32> {
+        let ((from_x, from_y), (to_x, to_y)) = (index_to_square(from), index_to_square(to));
+        let pushed = square_to_index((
+            (2 * to_x as i8 - from_x as i8) as u8,
+            (2 * to_y as i8 - from_y as i8) as u8,
+        ))?;
+        if (self.squares & !occupied & !self.levels[3]) & (1 << pushed) != 0 {
+            Some(pushed)
+        } else {
+            None
+        }
+    }
+
+    fn step_targets(&self, player_id: usize, from: u32, occupied: u32) -> u32 {
+        let height = self.height(from);
+        let highest = if self.can_move_up(player_id) {
+            height + 1
+        } else {
+            height
+        };
+        let reachable = NEIGHBOURS[from as usize] & self.at_most(highest.min(3));
+        let opponents = occupied & !self.team_workers(player_id);
+        let mut targets = reachable & !occupied;
+        match self.player_gods[player_id] {
+            Some(God::Apollo) => targets |= reachable & opponents,
+            Some(God::Minotaur) => {
+                for to in Bits(reachable & opponents) {
+                    if self.push_square(from, to, occupied).is_some() {
+                        targets |= 1 << to;
+                    }
+                }
+            }
+            _ => {}
+        }
+        targets
+    }
+
+    /// Squares that win the game when stepped onto from `from`
+    fn winning_squares(&self, player_id: usize, from: u32) -> u32 {
+        let height = self.height(from);
+        let mut winning = if height < 3 { self.levels[2] } else { 0 };
+        if self.player_gods[player_id] == Some(God::Pan) && height >= 2 {
+            winning |= self.at_most(height - 2);
+        }
+        if self.opposed_by_hera(player_id) {
+            winning &= !perimeter(mask_size(self.squares));
+        }
+        winning
+    }
+
+    /// Returns the squares the worker can move to, and which of those win the game
+    pub fn move_targets(&self, player_id: usize, worker: Worker) -> (u32, u32) {
+        let worker_mask = self.workers[player_id][worker as usize];
+        if worker_mask == 0 {
+            return (0, 0);
+        }
+        let from = worker_mask.trailing_zeros();
+        let occupied = self.occupied();
+        let mut targets = self.step_targets(player_id, from, occupied);
+        let mut winning = targets & self.winning_squares(player_id, from);
+        if self.player_gods[player_id] == Some(God::Artemis) {
+            for mid in Bits(targets & !winning) {
+                let second = self.step_targets(player_id, mid, occupied) & !worker_mask;
+                targets |= second;
+                winning |= second & self.winning_squares(player_id, mid);
+            }
+        }
+        (targets, winning)
+    }
+
+    /// Squares left unoccupied and undomed after the worker moves from `from` to `to`, that
+    /// can be built on from `to`
+    fn build_targets(&self, player_id: usize, from: u32, to: u32) -> u32 {
+        let occupied = self.occupied();
+        let mut occupied_after = (occupied & !(1 << from)) | (1 << to);
+        if occupied & (1 << to) != 0 {
+            occupied_after |= match self.player_gods[player_id] {
+                Some(God::Minotaur) => 1 << self.push_square(from, to, occupied).unwrap(),
+                _ => 1 << from,
+            };
+        }
+        NEIGHBOURS[to as usize] & self.squares & !occupied_after & !self.levels[3]
+    }
+
+    pub fn list_possible_actions(&self, player_id: usize) -> Vec<Action> {
+        let mut possible_actions: Vec<Action> = Vec::with_capacity(2 * 8 * 8);
+        possible_actions.extend(self.possible_actions(player_id));
+        possible_actions
+    }
+
+    /// The same actions as `list_possible_actions` in the same order, without allocating
+    pub fn possible_actions(&self, player_id: usize) -> Actions {
+        Actions {
+            moves: self.possible_moves(player_id),
+            no_domes: self.teammate_neighbours(player_id),
+            movement: None,
+            builds: 0,
+            build_targets: 0,
+            extra_builds: 0,
+            extra_base: None,
+            pending: None,
+        }
+    }
+
+    /// Each worker and square it can move to, without the builds that can follow. With a
+    /// limited supply of pieces there may not be any builds left after some of them. There are
+    /// none while a worker that has already moved is yet to build.
+    pub fn possible_moves(&self, player_id: usize) -> Moves {
+        Moves {
+            board: *self,
+            player_id,
+            next_worker: if self.effects.to_build.is_some() {
+                2
+            } else {
+                0
+            },
+            worker: Worker::One,
+            from: 0,
+            targets: 0,
+            winning: 0,
+        }
+    }
+}
+
+/// Iterator returned by `BitBoard::possible_moves`
+#[derive(Clone, Debug)]
+pub struct Moves {
+    board: BitBoard,
+    player_id: usize,
+    next_worker: usize,
+    worker: Worker,
+    from: u32,
+    // Squares the current worker can still move to, and which of them win
+    targets: u32,
+    winning: u32,
+}
+
+impl Moves {
+    /// The next worker, the squares it moves from and to, and whether the move wins
+    fn next_move(&mut self) -> Option<(Worker, u32, u32, bool)> {
+        while self.targets == 0 {
+            let worker = match self.next_worker {
+                0 => Worker::One,
+                1 => Worker::Two,
+                _ => return None,
+            };
+            self.next_worker += 1;
+            let (targets, winning) = self.board.move_targets(self.player_id, worker);
+            self.worker = worker;
+            self.targets = targets;
+            self.winning = winning;
+            self.from = self.board.workers[self.player_id][worker as usize].trailing_zeros();
+        }
+        let to = self.targets.trailing_zeros();
+        self.targets &= self.targets - 1;
+        Some((self.worker, self.from, to, self.winning & (1 << to) != 0))
+    }
+}
+
+impl Iterator for Moves {
+    type Item = (Worker, (u8, u8));
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_move()
+            .map(|(worker, _, to, _)| (worker, index_to_square(to)))
+    }
+}
+
+/// Iterator returned by `BitBoard::possible_actions`
+#[derive(Clone, Debug)]
+pub struct Actions {
+    moves: Moves,
+    // Squares the builds may not put a dome on
+    no_domes: u32,
+    // Worker and square of the move whose builds are being listed
+    movement: Option<(Worker, (u8, u8))>,
+    builds: u32,
+    build_targets: u32,
+    // Demeter's second builds still to list after `extra_base`
+    extra_builds: u32,
+    extra_base: Option<Action>,
+    // Atlas's dome or Hephaestus's second build, listed straight after the plain build
+    pending: Option<Action>,
+}
+
+impl Iterator for Actions {
+    type Item = Action;
+    fn next(&mut self) -> Option<Action> {
+        let board = self.moves.board;
+        let height = |square| board.height(square_to_index(square).unwrap());
+        loop {
+            let action = self.next_candidate()?;
+            // Winning moves don't build, so don't need any pieces or care about domes
+            if self.build_targets == 0 {
+                return Some(action);
+            }
+            let blocks_teammate = self.no_domes != 0
+                && domed_squares(&action, height)
+                    .iter()
+                    .flatten()
+                    .any(|&dome| self.no_domes & (1 << square_to_index(dome).unwrap()) != 0);
+            if !blocks_teammate && has_pieces_for(board.piece_supply, &action, height) {
+                return Some(action);
+            }
+        }
+    }
+}
+
+impl Actions {
+    /// The next action, before checking there are pieces left for its builds
+    fn next_candidate(&mut self) -> Option<Action> {
+        loop {
+            if let Some(action) = self.pending.take() {
+                return Some(action);
+            }
+            if let Some(action) = self.extra_base {
+                if self.extra_builds != 0 {
+                    let extra_build = self.extra_builds.trailing_zeros();
+                    self.extra_builds &= self.extra_builds - 1;
+                    return Some(Action {
+                        extra_build: Some(index_to_square(extra_build)),
+                        ..action
+                    });
+                }
+                self.extra_base = None;
+            }
+            if let (Some((worker, movement)), true) = (self.movement, self.builds != 0) {
+                let build = self.builds.trailing_zeros();
+                self.builds &= self.builds - 1;
+                let action = Action::new(worker, movement, index_to_square(build));
+                if self.build_targets == 0 {
+                    // Winning moves, which are listed with every neighbouring square
+                    return Some(action);
+                }
+                let board = &self.moves.board;
+                match board.player_gods[self.moves.player_id] {
+                    Some(God::Atlas) if board.levels[2] & (1 << build) == 0 => {
+                        self.pending = Some(Action {
+                            dome: true,
+                            ..action
+                        });
+                    }
+                    Some(God::Demeter) => {
+                        self.extra_base = Some(action);
+                        self.extra_builds = self.build_targets & !(1 << build);
+                    }
+                    Some(God::Hephaestus) if board.height(build) <= 1 => {
+                        self.pending = Some(Action {
+                            extra_build: Some(action.build),
+                            ..action
+                        });
+                    }
+                    _ => {}
+                }
+                return Some(action);
+            }
+            let (worker, from, to, winning) = self.moves.next_move()?;
+            self.movement = Some((worker, index_to_square(to)));
+            if winning {
+                self.builds = NEIGHBOURS[to as usize] & self.moves.board.squares;
+                self.build_targets = 0;
+            } else {
+                self.builds = self
+                    .moves
+                    .board
+                    .build_targets(self.moves.player_id, from, to);
+                self.build_targets = self.builds;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_game(rng: &mut rand::rngs::StdRng) -> Game {
+        let size = if rng.gen_bool(0.3) {
+            BoardSize::new(rng.gen_range(3, 6), rng.gen_range(3, 6)).unwrap()
+        } else {
+            BoardSize::FULL
+        };
+        let mut board = [[TowerStates::Empty; 5]; 5];
+        for (x, y) in size.squares() {
+            board[x as usize][y as usize] = [
+                TowerStates::Empty,
+                TowerStates::Empty,
+                TowerStates::Level1,
+                TowerStates::Level2,
+                TowerStates::Level3,
+                TowerStates::Capped,
+            ][rng.gen_range(0, 6)];
+        }
+        let mut squares: Vec<(u8, u8)> = size.squares().collect();
+        rand::seq::SliceRandom::shuffle(&mut squares[..], rng);
+        let mut player_locations = [((17, 17), (17, 17)); MAX_PLAYERS];
+        let mut player_statuses = [Status::Dead; MAX_PLAYERS];
+        let mut player_gods = [None; MAX_PLAYERS];
+        for player_id in 0..rng.gen_range(2, MAX_PLAYERS + 1) {
+            player_locations[player_id] = (squares[2 * player_id], squares[2 * player_id + 1]);
+            player_statuses[player_id] = Status::Playing;
+            let god = rng.gen_range(0, God::ALL.len() + 2);
+            player_gods[player_id] = God::ALL.get(god).copied();
+        }
+        // Workers stand on towers they could have climbed
+        for &(w1, w2) in player_locations.iter() {
+            for &(x, y) in [w1, w2].iter().filter(|&&w| size.contains(w)) {
+                if board[x as usize][y as usize] == TowerStates::Capped {
+                    board[x as usize][y as usize] = TowerStates::Level2;
+                }
+            }
+        }
+        let mut game = Game::new(board, player_locations, player_statuses);
+        game.size = size;
+        game.player_gods = player_gods;
+        game.teams = player_statuses[3] == Status::Playing && rng.gen_bool(0.5);
+        if let Some(athena) = player_gods.iter().position(|&god| god == Some(God::Athena)) {
+            if rng.gen_bool(0.5) {
+                game.effects.moved_up = Some(athena);
+            }
+        }
+        if rng.gen_bool(0.3) {
+            game.piece_supply = Some([
+                rng.gen_range(0, 3),
+                rng.gen_range(0, 3),
+                rng.gen_range(0, 3),
+                rng.gen_range(0, 3),
+            ]);
+        }
+        game.refresh_hash();
+        game
+    }
+
+    fn sort_key(action: &Action) -> String {
+        format!("{:?}", action)
+    }
+
+    fn brute_force_actions(game: &Game, player_id: usize) -> Vec<Action> {
+        let squares: Vec<(u8, u8)> = (0..25).map(index_to_square).collect();
+        let mut actions = Vec::new();
+        for &worker in [Worker::One, Worker::Two].iter() {
+            for &movement in squares
+                .iter()
+                .filter(|m| game.can_move_to_square(player_id, worker, **m))
+            {
+                for &build in squares.iter() {
+                    let action = Action::new(worker, movement, build);
+                    if game.is_winning_move(player_id, worker, movement) {
+                        if NEIGHBOURS[square_to_index(movement).unwrap() as usize]
+                            & (1 << square_to_index(build).unwrap())
+                            != 0
+                            && game.size.contains(build)
+                        {
+                            actions.push(action);
+                        }
+                        continue;
+                    }
+                    for &dome in [false, true].iter() {
+                        for extra_build in
+                            std::iter::once(None).chain(squares.iter().map(|s| Some(*s)))
+                        {
+                            let action = Action {
+                                dome,
+                                extra_build,
+                                ..action
+                            };
+                            if game.is_valid(player_id, action, false).is_ok() {
+                                actions.push(action);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    #[test]
+    fn converts_to_and_from_game() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let game = random_game(&mut rng);
+            assert_eq!(Game::from(BitBoard::from(game)), game);
+        }
+    }
+
+    #[test]
+    fn generates_exactly_the_valid_actions() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let game = random_game(&mut rng);
+            for player_id in
+                (0..MAX_PLAYERS).filter(|&i| game.player_statuses[i] == Status::Playing)
+            {
+                let mut generated = BitBoard::from(game).list_possible_actions(player_id);
+                let mut expected = brute_force_actions(&game, player_id);
+                generated.sort_by_key(sort_key);
+                expected.sort_by_key(sort_key);
+                assert_eq!(generated, expected, "{:?}", game);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_then_builds_make_the_listed_actions() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let game = random_game(&mut rng);
+            for player_id in
+                (0..MAX_PLAYERS).filter(|&i| game.player_statuses[i] == Status::Playing)
+            {
+                let mut game = game;
+                game.to_move = player_id;
+                game.refresh_hash();
+                let mut expected = game.list_possible_actions(player_id);
+                let legal_moves = game.legal_moves(player_id);
+                let mut actions = Vec::new();
+                for (worker, movement) in game.possible_moves(player_id) {
+                    let mut moved = game;
+                    let won = match moved.apply_move(player_id, worker, movement) {
+                        Ok(won) => won,
+                        Err(error) => {
+                            assert_eq!(error, IllegalAction::NoBuild);
+                            assert!(!legal_moves.contains(&(worker, movement)));
+                            continue;
+                        }
+                    };
+                    assert!(legal_moves.contains(&(worker, movement)));
+                    if won {
+                        actions.extend(expected.iter().filter(|action| {
+                            (action.worker, action.movement) == (worker, movement)
+                        }));
+                        continue;
+                    }
+                    assert!(moved.list_possible_actions(player_id).is_empty());
+                    for build in moved.legal_builds(player_id, worker) {
+                        let action = Action::with_build(worker, movement, build);
+                        let mut built = moved;
+                        built.apply_build(player_id, build).unwrap();
+                        let mut applied = game;
+                        applied.apply_action(player_id, action, false).unwrap();
+                        assert_eq!(built, applied, "{:?}", action);
+                        actions.push(action);
+                    }
+                }
+                actions.sort_by_key(sort_key);
+                expected.sort_by_key(sort_key);
+                assert_eq!(actions, expected, "{:?}", game);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_are_the_squares_workers_can_move_to() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        for _ in 0..200 {
+            let game = random_game(&mut rng);
+            for player_id in 0..MAX_PLAYERS {
+                let mut expected = Vec::new();
+                for &worker in [Worker::One, Worker::Two].iter() {
+                    for movement in (0..25).map(index_to_square) {
+                        if game.can_move_to_square(player_id, worker, movement) {
+                            expected.push((worker, movement));
+                        }
+                    }
+                }
+                let moves: Vec<(Worker, (u8, u8))> = game.possible_moves(player_id).collect();
+                assert_eq!(moves, expected, "{:?}", game);
+            }
+        }
+    }
+}
@@ -9,7 +9,7 @@ pub struct GamePrediction<A: nn::ActivationFunction> {
 
 fn get_total_height(game: &Game, player_id: usize) -> u8 {
     let (w1, w2) = game.player_locations[player_id];
-    if w1.0 >= 5 || w1.1 >= 5 || w2.0 >= 5 || w2.1 >= 5 {
+    if !game.size.contains(w1) || !game.size.contains(w2) {
         0
     } else {
         game.board[w1.0 as usize][w1.1 as usize].to_int()
@@ -17,9 +17,10 @@ fn get_total_height(game: &Game, player_id: usize) -> u8 {
     }
 }
 
+#[allow(dead_code)]
 fn get_max_height(game: &Game, player_id: usize) -> u8 {
     let (w1, w2) = game.player_locations[player_id];
-    if w1.0 >= 5 || w1.1 >= 5 || w2.0 >= 5 || w2.1 >= 5 {
+    if !game.size.contains(w1) || !game.size.contains(w2) {
         0
     } else {
         game.board[w1.0 as usize][w1.1 as usize]
@@ -28,21 +29,20 @@ fn get_max_height(game: &Game, player_id: usize) -> u8 {
     }
 }
 
+#[allow(dead_code)]
 fn squares_movable_to(game: &Game, player_id: usize) -> u8 {
     let mut count = 0;
     let (w1, w2) = game.player_locations[player_id];
     for &((wx, wy), worker) in &[(w1, Worker::One), (w2, Worker::Two)] {
-        if wx >= 5 || wy >= 5 {
+        if !game.size.contains((wx, wy)) {
             continue;
         }
         for &mx in &[wx.saturating_sub(1), wx, wx + 1] {
-            if mx < 5 {
-                for &my in &[wy.saturating_sub(1), wy, wy + 1] {
-                    if my < 5 {
-                        if game.can_move_to_square(player_id, worker, (mx, my)) {
-                            count += 1;
-                        }
-                    }
+            for &my in &[wy.saturating_sub(1), wy, wy + 1] {
+                if game.size.contains((mx, my))
+                    && game.can_move_to_square(player_id, worker, (mx, my))
+                {
+                    count += 1;
                 }
             }
         }
@@ -50,37 +50,52 @@ fn squares_movable_to(game: &Game, player_id: usize) -> u8 {
     count
 }
 
+/// Up to two opponents still in the game, in the order they play after `player_id`
+fn opponents(game: &Game, player_id: usize) -> [Option<usize>; 2] {
+    let mut opponents = (1..MAX_PLAYERS)
+        .map(|offset| (player_id + offset) % MAX_PLAYERS)
+        .filter(|&other| {
+            game.player_statuses[other] == Status::Playing && !game.are_teammates(player_id, other)
+        });
+    [opponents.next(), opponents.next()]
+}
+
+impl<A: nn::ActivationFunction> Default for GamePrediction<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<A: nn::ActivationFunction> GamePrediction<A> {
     pub fn new() -> Self {
         Self {
             network: nn::NeuralNet::new(INPUT_COUNT),
         }
     }
-	
-	pub fn create_random(rng: &mut rand::rngs::ThreadRng) -> Self {
+
+    pub fn create_random(rng: &mut rand::rngs::ThreadRng) -> Self {
         Self {
             network: nn::NeuralNet::create_random(INPUT_COUNT, rng),
         }
     }
-	
-	
 
     fn generate_input(&self, game: &Game, player_id: usize) -> [f32; INPUT_COUNT] {
         let mut input = [0.0; INPUT_COUNT];
+        // Missing opponents are left at 0
+        let [first, second] = opponents(game, player_id);
         input[0] = game.can_win_on_next_turn(player_id) as u8 as f32;
-        input[1] = game.can_win_on_next_turn((player_id + 1) % 3) as u8 as f32;
-        input[2] = game.can_win_on_next_turn((player_id + 2) % 3) as u8 as f32;
+        input[1] = first.map_or(0.0, |other| game.can_win_on_next_turn(other) as u8 as f32);
+        input[2] = second.map_or(0.0, |other| game.can_win_on_next_turn(other) as u8 as f32);
         input[3] = get_total_height(game, player_id) as f32;
-        input[4] = get_total_height(game, (player_id + 1) % 3) as f32;
-        input[5] = get_total_height(game, (player_id + 2) % 3) as f32;
-		/*
+        input[4] = first.map_or(0.0, |other| get_total_height(game, other) as f32);
+        input[5] = second.map_or(0.0, |other| get_total_height(game, other) as f32);
+        /*
         input[6] = get_max_height(game, player_id) as f32;
-        input[7] = get_max_height(game, (player_id + 1) % 3) as f32;
-        input[8] = get_max_height(game, (player_id + 2) % 3) as f32;
-		input[9] = squares_movable_to(game, player_id) as f32;
-        input[10] = squares_movable_to(game, (player_id + 1) % 3) as f32;
-        input[11] = squares_movable_to(game, (player_id + 2) % 3) as f32;*/
-		
+        input[7] = first.map_or(0.0, |other| get_max_height(game, other) as f32);
+        input[8] = second.map_or(0.0, |other| get_max_height(game, other) as f32);
+        input[9] = squares_movable_to(game, player_id) as f32;
+        input[10] = first.map_or(0.0, |other| squares_movable_to(game, other) as f32);
+        input[11] = second.map_or(0.0, |other| squares_movable_to(game, other) as f32);*/
 
         input
     }
@@ -89,7 +104,21 @@ impl<A: nn::ActivationFunction> GamePrediction<A> {
         self.network.predict(&self.generate_input(game, player_id))
     }
 
-    pub fn learn(&mut self, games: &[(Game, usize, bool)], iterations: usize, step_size: f32) {
+    /// With `augment` every position is also learned in each rotation and reflection of the board
+    pub fn learn(
+        &mut self,
+        games: &[(Game, usize, bool)],
+        iterations: usize,
+        step_size: f32,
+        augment: bool,
+    ) {
+        let augmented;
+        let games = if augment {
+            augmented = symmetry::augment_games(games);
+            &augmented[..]
+        } else {
+            games
+        };
         let mut training_data: Vec<(f32, [f32; INPUT_COUNT])> = Vec::new();
         for (game, player_id, success) in games.iter() {
             training_data.push((
@@ -0,0 +1,344 @@
+use crate::*;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Phase {
+    /// Players take turns placing both of their workers
+    Placement,
+    Play,
+    Finished,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Elimination {
+    pub player_id: usize,
+    /// The turn the player was eliminated on
+    pub turn: usize,
+    pub reason: EliminationReason,
+}
+
+/// A game from worker placement through to a winner, enforcing whose turn it is. Players with no
+/// legal action are eliminated as soon as their turn comes up.
+#[derive(Debug, Clone)]
+pub struct GameState {
+    game: Game,
+    phase: Phase,
+    winner: Option<usize>,
+    start_locations: Vec<StartLocation>,
+    actions: Vec<(usize, Action)>,
+    eliminations: Vec<Elimination>,
+    seed: Option<u64>,
+}
+
+impl GameState {
+    /// Starts a game on an empty board. Players with a `Dead` status don't take part.
+    pub fn new(
+        player_statuses: [Status; MAX_PLAYERS],
+        player_gods: [Option<God>; MAX_PLAYERS],
+    ) -> Self {
+        let mut game = Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            [((17, 17), (17, 17)); MAX_PLAYERS],
+            player_statuses,
+        );
+        game.set_player_gods(player_gods);
+        Self {
+            game,
+            phase: Phase::Placement,
+            winner: None,
+            start_locations: Vec::new(),
+            actions: Vec::new(),
+            eliminations: Vec::new(),
+            seed: None,
+        }
+    }
+
+    /// Plays with a limited supply of building pieces, see `Game::limit_pieces`
+    pub fn limit_pieces(&mut self) {
+        self.game.limit_pieces();
+    }
+
+    /// Plays on a smaller board, before any workers are placed. See `BoardSize`.
+    pub fn set_board_size(&mut self, size: BoardSize) {
+        self.game.set_board_size(size);
+    }
+
+    /// Plays seats 0 and 2 against seats 1 and 3, see `Game::play_in_teams`
+    pub fn play_in_teams(&mut self) {
+        self.game.play_in_teams();
+    }
+
+    /// Seeds the random choices of the players, see `play_game`
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// The player who places workers or acts next
+    pub fn current_player(&self) -> usize {
+        self.game.to_move
+    }
+
+    /// Number of actions played so far, not counting worker placement
+    pub fn turn(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Worker placements so far, in the order they were made
+    pub fn start_locations(&self) -> &[StartLocation] {
+        &self.start_locations
+    }
+
+    /// Actions played so far with the player who played them
+    pub fn actions(&self) -> &[(usize, Action)] {
+        &self.actions
+    }
+
+    pub fn eliminations(&self) -> &[Elimination] {
+        &self.eliminations
+    }
+
+    /// The winner, once the game is finished. In team play it's the player who won for their
+    /// team, see `has_won`.
+    pub fn result(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Whether the player won, on their own or along with their teammate
+    pub fn has_won(&self, player_id: usize) -> bool {
+        match self.winner {
+            Some(winner) => self.game.are_teammates(winner, player_id),
+            None => false,
+        }
+    }
+
+    pub fn place_workers(&mut self, start_location: StartLocation) -> Result<(), IllegalPlacement> {
+        if self.phase != Phase::Placement {
+            return Err(IllegalPlacement::NotPlacing);
+        }
+        let player_id = self.game.to_move;
+        self.game.place_workers(player_id, start_location)?;
+        self.start_locations.push(start_location);
+        let next_player = self.game.next_player(player_id);
+        self.game.set_to_move(next_player);
+        self.start_play_once_placed();
+        Ok(())
+    }
+
+    /// Legal actions for the current player, which are only empty outside of the play phase
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if self.phase == Phase::Play {
+            self.game.list_possible_actions(self.game.to_move)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Plays the action for the current player. An illegal action is rejected without changing
+    /// anything.
+    pub fn play(&mut self, action: Action) -> Result<(), IllegalAction> {
+        if self.phase != Phase::Play {
+            return Err(IllegalAction::NotInPlay);
+        }
+        let player_id = self.game.to_move;
+        let undo = self.game.apply_action(player_id, action, false)?;
+        self.actions.push((player_id, action));
+        if undo.won {
+            self.finish(player_id);
+        } else {
+            self.eliminate_stuck_players();
+        }
+        Ok(())
+    }
+
+    /// Eliminates the current player, e.g. for having made an illegal action or placement
+    pub fn forfeit(&mut self, reason: EliminationReason) {
+        match self.phase {
+            Phase::Placement => {
+                self.eliminate(reason);
+                self.start_play_once_placed();
+            }
+            Phase::Play => {
+                self.eliminate(reason);
+                self.eliminate_stuck_players();
+            }
+            Phase::Finished => {}
+        }
+    }
+
+    fn start_play_once_placed(&mut self) {
+        // Placement goes round once, so it's over when the first player is back to move
+        if self.phase == Phase::Placement
+            && self.game.worker_location(self.game.to_move, Worker::One) != (17, 17)
+        {
+            self.phase = Phase::Play;
+            self.eliminate_stuck_players();
+        }
+    }
+
+    fn eliminate(&mut self, reason: EliminationReason) {
+        let player_id = self.game.to_move;
+        self.eliminations.push(Elimination {
+            player_id,
+            turn: self.turn(),
+            reason,
+        });
+        self.game.eliminate(player_id);
+        if self.game.one_side_left() {
+            let survivor =
+                (0..MAX_PLAYERS).find(|&i| self.game.player_statuses[i] == Status::Playing);
+            if let Some(winner) = survivor {
+                self.finish(winner);
+            }
+        }
+    }
+
+    fn eliminate_stuck_players(&mut self) {
+        while self.phase == Phase::Play
+            && self
+                .game
+                .possible_actions(self.game.to_move)
+                .next()
+                .is_none()
+        {
+            self.eliminate(EliminationReason::NoLegalAction);
+        }
+    }
+
+    fn finish(&mut self, winner: usize) {
+        self.winner = Some(winner);
+        self.phase = Phase::Finished;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed_state() -> GameState {
+        let mut state = GameState::new(Status::seats(3), [None; MAX_PLAYERS]);
+        for &start in [((0, 0), (0, 1)), ((2, 2), (2, 3)), ((4, 4), (4, 3))].iter() {
+            assert_eq!(state.phase(), Phase::Placement);
+            state.place_workers(start).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn placement_then_play_in_turn_order() {
+        let mut state = GameState::new(
+            [Status::Playing, Status::Dead, Status::Playing, Status::Dead],
+            [None; MAX_PLAYERS],
+        );
+        assert_eq!(state.current_player(), 0);
+        assert!(state.legal_actions().is_empty());
+        state.place_workers(((0, 0), (0, 1))).unwrap();
+        assert_eq!(state.current_player(), 2);
+        assert_eq!(
+            state.place_workers(((3, 3), (0, 1))),
+            Err(IllegalPlacement::Occupied)
+        );
+        state.place_workers(((4, 4), (4, 3))).unwrap();
+        assert_eq!(state.phase(), Phase::Play);
+        assert_eq!(state.current_player(), 0);
+
+        state.play(state.legal_actions()[0]).unwrap();
+        assert_eq!(state.turn(), 1);
+        assert_eq!(state.current_player(), 2);
+        assert_eq!(
+            state.play(Action::new(Worker::One, (1, 1), (1, 2))),
+            Err(IllegalAction::NotAdjacent)
+        );
+        assert_eq!(state.turn(), 1);
+    }
+
+    #[test]
+    fn teammates_share_the_win() {
+        let mut state = GameState::new(Status::seats(4), [None; MAX_PLAYERS]);
+        state.play_in_teams();
+        for &start in [
+            ((0, 0), (0, 1)),
+            ((2, 2), (2, 3)),
+            ((4, 4), (4, 3)),
+            ((0, 4), (1, 4)),
+        ]
+        .iter()
+        {
+            state.place_workers(start).unwrap();
+        }
+        state.play(state.legal_actions()[0]).unwrap();
+        state.forfeit(EliminationReason::IllegalAction(IllegalAction::NotAdjacent));
+        assert_eq!(state.phase(), Phase::Play);
+        assert_eq!(state.current_player(), 2);
+        state.play(state.legal_actions()[0]).unwrap();
+        state.forfeit(EliminationReason::IllegalAction(IllegalAction::NotAdjacent));
+        assert_eq!(state.phase(), Phase::Finished);
+        assert!(state.has_won(0) && state.has_won(2));
+        assert!(!state.has_won(1) && !state.has_won(3));
+    }
+
+    #[test]
+    fn forfeiting_a_placement_skips_the_player() {
+        let mut state = GameState::new(Status::seats(3), [None; MAX_PLAYERS]);
+        state.place_workers(((0, 0), (0, 1))).unwrap();
+        state.forfeit(EliminationReason::IllegalPlacement(
+            IllegalPlacement::Occupied,
+        ));
+        assert_eq!(state.current_player(), 2);
+        state.place_workers(((4, 4), (4, 3))).unwrap();
+        assert_eq!(state.phase(), Phase::Play);
+        assert_eq!(state.current_player(), 0);
+
+        let mut state = GameState::new(Status::seats(2), [None; MAX_PLAYERS]);
+        state.forfeit(EliminationReason::IllegalPlacement(
+            IllegalPlacement::SameSquare,
+        ));
+        assert_eq!(state.result(), Some(1));
+    }
+
+    #[test]
+    fn stuck_players_are_eliminated_until_one_wins() {
+        let mut state = placed_state();
+        state.game.board[4][2] = TowerStates::Capped;
+        state.game.board[3][2] = TowerStates::Capped;
+        state.game.board[3][3] = TowerStates::Capped;
+        state.game.board[3][4] = TowerStates::Capped;
+        state.game.refresh_hash();
+        state
+            .play(Action::new(Worker::One, (1, 0), (0, 0)))
+            .unwrap();
+        state
+            .play(Action::new(Worker::One, (1, 2), (2, 2)))
+            .unwrap();
+        assert_eq!(state.current_player(), 0);
+        assert_eq!(
+            state.eliminations(),
+            &[Elimination {
+                player_id: 2,
+                turn: 2,
+                reason: EliminationReason::NoLegalAction
+            }]
+        );
+        assert_eq!(state.game().player_locations[2], ((17, 17), (17, 17)));
+
+        state.forfeit(EliminationReason::IllegalAction(
+            IllegalAction::ClimbTooHigh,
+        ));
+        assert_eq!(state.phase(), Phase::Finished);
+        assert_eq!(state.result(), Some(1));
+        assert_eq!(
+            state.play(Action::new(Worker::One, (0, 0), (1, 0))),
+            Err(IllegalAction::NotInPlay)
+        );
+    }
+}
@@ -1,8 +1,6 @@
 use crate::*;
-use rand::prelude::*;
-use rayon::prelude::*;
-
 use rand::seq::SliceRandom;
+use rand::Rng;
 
 const GENE_COUNT: usize = 4;
 const START_LOCATION_GENE_COUNT: usize = 3;
@@ -25,6 +23,7 @@ const START_LOCATION_GENES: [&dyn StartScorer; START_LOCATION_GENE_COUNT] = [
 pub type TrainingData = (bool, usize, Game, Action);
 
 pub trait ActionScorer: Sync + Send {
+    #[allow(clippy::too_many_arguments)]
     fn get_score(
         &self,
         game: &Game,
@@ -41,7 +40,7 @@ pub trait ActionScorer: Sync + Send {
 pub trait StartScorer: Sync + Send {
     fn get_score(
         &self,
-        player_locations: &[((u8, u8), (u8, u8))],
+        player_locations: &[StartLocation],
         start_locations: (u8, u8),
         other_starting_location: Option<(u8, u8)>,
     ) -> f32;
@@ -62,6 +61,12 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
     }
 }
 
+impl<A: nn::ActivationFunction> Default for GeneticAI<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<A: nn::ActivationFunction> GeneticAI<A> {
     fn get_unprocessed(
         &self,
@@ -92,7 +97,7 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
 
     fn get_unprocessed_starting_location(
         &self,
-        player_locations: &[((u8, u8), (u8, u8))],
+        player_locations: &[StartLocation],
         start_locations: (u8, u8),
         other_starting_location: Option<(u8, u8)>,
     ) -> [f32; START_LOCATION_GENE_COUNT] {
@@ -126,7 +131,7 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
 
     fn get_start_location_score(
         &self,
-        player_locations: &[((u8, u8), (u8, u8))],
+        player_locations: &[StartLocation],
         start_locations: (u8, u8),
         other_starting_location: Option<(u8, u8)>,
     ) -> f32 {
@@ -148,9 +153,17 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
         }
     }
 
-    pub fn learn(&mut self, results: &[TrainingData], iterations: usize) {
+    /// With `augment` every action is also learned in each rotation and reflection of the board
+    pub fn learn(&mut self, results: &[TrainingData], iterations: usize, augment: bool) {
+        let augmented;
+        let results = if augment {
+            augmented = symmetry::augment_training_data(results);
+            &augmented[..]
+        } else {
+            results
+        };
         let mut training_data: Vec<(f32, [f32; GENE_COUNT])> = Vec::new();
-        for (success, player_id, game, (worker, movement, build)) in results.iter() {
+        for (success, player_id, game, action) in results.iter() {
             // Generate more training data
             // for (success, (worker, movement, build)) in game
             //     .list_possible_actions(*player_id)
@@ -162,16 +175,10 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
             let unprocessed = self.get_unprocessed(
                 game,
                 *player_id,
-                *worker,
-                *movement,
-                *build,
-                game.is_near_player(
-                    *player_id,
-                    match *worker {
-                        Worker::One => game.player_locations[*player_id as usize].0,
-                        Worker::Two => game.player_locations[*player_id as usize].1,
-                    },
-                ),
+                action.worker,
+                action.movement,
+                action.build,
+                game.is_near_player(*player_id, game.worker_location(*player_id, action.worker)),
             );
             training_data.push((if *success { 1.0 } else { -1.0 }, unprocessed));
         }
@@ -190,56 +197,40 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
 
             let mut results: Vec<TrainingData> = Vec::new();
             for _ in 0..batch_size {
-                let mut action_history: Option<[Vec<(Game, Action)>; 3]> =
-                    Some([vec![], vec![], vec![]]);
-                let tmp_players: [Option<&dyn Player>; 3] = [Some(self), Some(self), None];
-                let result = main_loop(tmp_players, false, &mut action_history, &mut None);
-                for (player_id, action_list) in action_history.unwrap().iter().enumerate() {
-                    for (game, action) in action_list.iter() {
-                        results.push((player_id == result, player_id, *game, *action));
-                    }
-                }
+                let tmp_players: [Option<&dyn Player>; MAX_PLAYERS] =
+                    [Some(self), Some(self), None, None];
+                let record = main_loop(tmp_players, [None; MAX_PLAYERS], &mut []);
+                results.extend(record.training_data().unwrap());
             }
-            self.learn(&results, 100);
+            self.learn(&results, 100, false);
         }
     }
-    pub fn train(&mut self, players: Vec<Box<(dyn Player)>>, iterations: usize, batch_size: usize) {
+    pub fn train(&mut self, players: Vec<Box<dyn Player>>, iterations: usize, batch_size: usize) {
         let mut total_win_count = 0;
         for iteration in 0..iterations {
             let mut win_count = 0;
             let mut results: Vec<TrainingData> = Vec::new();
             for _ in 0..batch_size {
                 for player in players.iter() {
-                    let mut action_history1: Option<[Vec<(Game, Action)>; 3]> =
-                        Some([vec![], vec![], vec![]]);
-                    let tmp_players: [Option<&dyn Player>; 3] = [Some(self), Some(&**player), None];
-                    let result1 = main_loop(tmp_players, false, &mut action_history1, &mut None);
-                    if result1 == 0 {
+                    let tmp_players: [Option<&dyn Player>; MAX_PLAYERS] =
+                        [Some(self), Some(&**player), None, None];
+                    let record1 = main_loop(tmp_players, [None; MAX_PLAYERS], &mut []);
+                    if record1.winner == Some(0) {
                         win_count += 1;
                     }
 
-                    let mut action_history2: Option<[Vec<(Game, Action)>; 3]> =
-                        Some([vec![], vec![], vec![]]);
-                    let tmp_players: [Option<&dyn Player>; 3] = [Some(&**player), Some(self), None];
-                    let result2 = main_loop(tmp_players, false, &mut action_history2, &mut None);
-                    if result2 == 1 {
+                    let tmp_players: [Option<&dyn Player>; MAX_PLAYERS] =
+                        [Some(&**player), Some(self), None, None];
+                    let record2 = main_loop(tmp_players, [None; MAX_PLAYERS], &mut []);
+                    if record2.winner == Some(1) {
                         win_count += 1;
                     }
-                    for (result, action_history) in
-                        [(result1, action_history1), (result2, action_history2)].iter()
-                    {
-                        for (player_id, action_list) in
-                            action_history.as_ref().unwrap().iter().enumerate()
-                        {
-                            for (game, action) in action_list.iter() {
-                                results.push((player_id == *result, player_id, *game, *action));
-                            }
-                        }
-                    }
+                    results.extend(record1.training_data().unwrap());
+                    results.extend(record2.training_data().unwrap());
                 }
             }
             total_win_count += win_count;
-            self.learn(&results, 100);
+            self.learn(&results, 100, false);
             println!(
                 "Iteration: {}, wins: {}, total_wins: {}",
                 iteration,
@@ -252,64 +243,48 @@ impl<A: nn::ActivationFunction> GeneticAI<A> {
 }
 impl<A: nn::ActivationFunction> Player for GeneticAI<A> {
     fn get_action(&self, game: &Game, player_id: usize) -> Action {
-        let actions = game.list_possible_actions(player_id);
-        if actions.is_empty() {
-            (Worker::One, (0, 0), (0, 0))
-        } else {
-            let location = game.player_locations[player_id];
-            let w1_is_near_player = game.is_near_player(player_id, location.0);
-            let w2_is_near_player = game.is_near_player(player_id, location.1);
-            let action_scores = actions
-                .iter()
-                .map(|(worker, movement, build)| {
-                    ((*worker, *movement, *build), {
-                        self.get_score(
-                            game,
-                            player_id,
-                            *worker,
-                            *movement,
-                            *build,
-                            if *worker == Worker::One {
-                                w1_is_near_player
-                            } else {
-                                w2_is_near_player
-                            },
-                        )
-                    })
-                })
-                .collect::<Vec<(Action, f32)>>();
-
-            let mut max = f32::MIN;
-            for (_, score) in action_scores.iter() {
-                if *score > max {
-                    max = *score;
+        let location = game.player_locations[player_id];
+        let w1_is_near_player = game.is_near_player(player_id, location.0);
+        let w2_is_near_player = game.is_near_player(player_id, location.1);
+        let mut best = None;
+        let mut max = f32::MIN;
+        let mut ties = 0;
+        with_rng(|rng| {
+            for action in game.possible_actions(player_id) {
+                let score = self.get_score(
+                    game,
+                    player_id,
+                    action.worker,
+                    action.movement,
+                    action.build,
+                    if action.worker == Worker::One {
+                        w1_is_near_player
+                    } else {
+                        w2_is_near_player
+                    },
+                );
+                // Picks uniformly between the best actions without collecting them. The first
+                // action is kept even if its score is NaN, so there is always one to play.
+                if best.is_none() || score > max {
+                    max = score;
+                    ties = 1;
+                    best = Some(action);
+                } else if score == max {
+                    ties += 1;
+                    if rng.gen_range(0, ties) == 0 {
+                        best = Some(action);
+                    }
                 }
             }
-            if max == f32::MIN {
-                return (Worker::One, (0, 0), (0, 0));
-            }
-            let options = action_scores
-                .iter()
-                .filter(|(_, score)| *score == max)
-                .map(|(action, _)| *action)
-                .collect::<Vec<Action>>();
-            *options.choose(&mut rand::thread_rng()).unwrap()
-        }
+        });
+        best.unwrap()
     }
     fn get_starting_position(
         &self,
-        _: &Game,
-        player_locations: &[((u8, u8), (u8, u8))],
-    ) -> ((u8, u8), (u8, u8)) {
-        let mut values: Vec<(u8, u8)> = Vec::new();
-        for i in (0..25).map(|val| (val / 5, val % 5)) {
-            if player_locations
-                .iter()
-                .all(|&(val1, val2)| val1 != i && val2 != i)
-            {
-                values.push(i);
-            }
-        }
+        game: &Game,
+        player_locations: &[StartLocation],
+    ) -> StartLocation {
+        let values: Vec<(u8, u8)> = game.free_squares(player_locations).collect();
         let first_start_location_scores = values
             .iter()
             .map(|location| {
@@ -330,7 +305,7 @@ impl<A: nn::ActivationFunction> Player for GeneticAI<A> {
             .filter(|(_, score)| *score == max.1)
             .map(|(action, _)| *action)
             .collect::<Vec<(u8, u8)>>();
-        let first_location = *options.choose(&mut rand::thread_rng()).unwrap();
+        let first_location = *with_rng(|rng| options.choose(rng)).unwrap();
 
         let second_start_location_scores = values
             .iter()
@@ -357,29 +332,25 @@ impl<A: nn::ActivationFunction> Player for GeneticAI<A> {
             .filter(|(_, score)| *score == max.1)
             .map(|(action, _)| *action)
             .collect::<Vec<(u8, u8)>>();
-        let second_location = *options.choose(&mut rand::thread_rng()).unwrap();
+        let second_location = *with_rng(|rng| options.choose(rng)).unwrap();
         (first_location, second_location)
     }
 }
 
-fn compare_ai(ai1: &dyn Player, ai2: &dyn Player, matches: usize) -> (usize, usize) {
-    let mut scores = (0, 0);
-    for _ in 0..matches {
-        let players: [Option<&dyn Player>; 3] = [Some(ai1), Some(ai2), None];
-        let result = main_loop(players, false, &mut None, &mut None);
-        if result == 0 {
-            scores.0 += 1
-        } else {
-            scores.1 += 1
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let players: [Option<&dyn Player>; 3] = [Some(ai2), Some(ai1), None];
-        let result = main_loop(players, false, &mut None, &mut None);
-        if result == 0 {
-            scores.1 += 1
-        } else {
-            scores.0 += 1
+    #[test]
+    fn plays_an_action_whatever_the_scores() {
+        let game =
+            Game::from_position_string("00000/00000/00000/00000/00000 A1,C3/E5,E4/-,- PPD 0")
+                .unwrap();
+        for &weight in [f32::NAN, f32::NEG_INFINITY].iter() {
+            let mut ai = GeneticAI::<nn::Tanh>::new();
+            ai.gene_weighting = nn::NeuralNet::from_weights(vec![weight; GENE_COUNT + 1]);
+            let action = ai.get_action(&game, 0);
+            assert!(game.possible_actions(0).any(|legal| legal == action));
         }
     }
-    scores
 }
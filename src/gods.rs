@@ -0,0 +1,67 @@
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, serde::Serialize, serde::Deserialize)]
+pub enum God {
+    /// May move into an opponent's square, forcing their worker into the square just vacated
+    Apollo,
+    /// May move one additional time, but not back to the square it started on
+    Artemis,
+    /// May build a dome at any level
+    Atlas,
+    /// May build one additional time, but not on the same square
+    Demeter,
+    /// May build one additional block (not a dome) on top of the first block
+    Hephaestus,
+    /// May move into an opponent's square if their worker can be pushed one square straight back
+    /// onto an unoccupied square without a dome
+    Minotaur,
+    /// Also wins by moving down two or more levels
+    Pan,
+    /// If one of her workers moved up, opponent workers can't move up until her next turn
+    Athena,
+    /// Opponents can't win by moving onto a square on the edge of the board
+    Hera,
+}
+
+impl God {
+    pub const ALL: [God; 9] = [
+        God::Apollo,
+        God::Artemis,
+        God::Atlas,
+        God::Demeter,
+        God::Hephaestus,
+        God::Minotaur,
+        God::Pan,
+        God::Athena,
+        God::Hera,
+    ];
+}
+
+impl std::fmt::Display for God {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            God::Apollo => "Apollo",
+            God::Artemis => "Artemis",
+            God::Atlas => "Atlas",
+            God::Demeter => "Demeter",
+            God::Hephaestus => "Hephaestus",
+            God::Minotaur => "Minotaur",
+            God::Pan => "Pan",
+            God::Athena => "Athena",
+            God::Hera => "Hera",
+        })
+    }
+}
+
+/// What earlier turns left behind for the turns after them, and how far through its turn the
+/// player to move is. `Game::apply_action` keeps it up to date.
+#[derive(
+    Debug, Default, PartialEq, Eq, Copy, Clone, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub struct TurnEffects {
+    /// The Athena player who moved up on their last turn, which stops their opponents moving
+    /// up until they play again
+    pub moved_up: Option<usize>,
+    /// The worker that has moved this turn and still has to build, between `Game::apply_move`
+    /// and `Game::apply_build`
+    #[serde(default)]
+    pub to_build: Option<crate::Worker>,
+}
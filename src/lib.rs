@@ -1,27 +1,286 @@
+pub mod action_index;
 mod action_score_algorithms;
+pub mod bitboard;
 pub mod bruteforce;
-mod first_choice_player;
+pub mod first_choice_player;
 pub mod genetic_ai;
-mod random_choice_player;
+pub mod random_choice_player;
 mod start_location_score_algorithms;
 
 pub mod game_prediction;
+pub mod game_state;
+pub mod gods;
 pub mod nn;
+pub mod notation;
+pub mod observer;
+pub mod perft;
+pub mod record;
+pub mod replay;
+pub mod session;
+pub mod symmetry;
+pub mod tournament;
+pub mod zobrist;
+
+pub use game_state::{GameState, Phase};
+pub use gods::{God, TurnEffects};
+pub use observer::Observer;
+pub use record::GameRecord;
+pub use session::{Event, GameSession};
+pub use symmetry::Symmetry;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Action {
+    pub worker: Worker,
+    pub movement: (u8, u8),
+    pub build: (u8, u8),
+    // Second build for Demeter or Hephaestus
+    #[serde(default)]
+    pub extra_build: Option<(u8, u8)>,
+    // Atlas building a dome straight onto a lower level
+    #[serde(default)]
+    pub dome: bool,
+}
+
+impl Action {
+    pub fn new(worker: Worker, movement: (u8, u8), build: (u8, u8)) -> Self {
+        Self {
+            worker,
+            movement,
+            build,
+            extra_build: None,
+            dome: false,
+        }
+    }
+
+    /// The action made of a move followed by `build`
+    pub fn with_build(worker: Worker, movement: (u8, u8), build: Build) -> Self {
+        Self {
+            worker,
+            movement,
+            build: build.build,
+            extra_build: build.extra_build,
+            dome: build.dome,
+        }
+    }
+
+    pub fn builds(&self) -> Build {
+        Build {
+            build: self.build,
+            extra_build: self.extra_build,
+            dome: self.dome,
+        }
+    }
+}
+
+/// The building half of an action, for playing a turn a step at a time with `Game::apply_move`
+/// and `Game::apply_build`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Build {
+    pub build: (u8, u8),
+    pub extra_build: Option<(u8, u8)>,
+    pub dome: bool,
+}
+
+/// Why `Game::is_valid` or `Game::apply_action` rejected an action
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum IllegalAction {
+    OutOfBounds,
+    OccupiedDestination,
+    ClimbTooHigh,
+    NotAdjacent,
+    MoveOntoDome,
+    BuildOnDome,
+    BuildOnDestination,
+    BuildOnOccupied,
+    /// The worker isn't on the board, or its player is out of the game
+    UnknownWorker,
+    /// Only Atlas may build a dome below level 3
+    DomeNotAllowed,
+    /// The extra build doesn't follow the rules of Demeter or Hephaestus
+    ExtraBuildNotAllowed,
+    /// The limited supply has run out of a piece the builds need
+    OutOfPieces,
+    /// Team play doesn't allow a dome next to a teammate's worker
+    BlocksTeammate,
+    /// Athena moved up on her last turn, so her opponents can't
+    MoveUpBlocked,
+    /// The move would leave the worker with nowhere it may build
+    NoBuild,
+    /// The game isn't in the phase for this, e.g. it is already over
+    NotInPlay,
+}
+
+impl IllegalAction {
+    pub const ALL: [IllegalAction; 16] = [
+        IllegalAction::OutOfBounds,
+        IllegalAction::OccupiedDestination,
+        IllegalAction::ClimbTooHigh,
+        IllegalAction::NotAdjacent,
+        IllegalAction::MoveOntoDome,
+        IllegalAction::BuildOnDome,
+        IllegalAction::BuildOnDestination,
+        IllegalAction::BuildOnOccupied,
+        IllegalAction::UnknownWorker,
+        IllegalAction::DomeNotAllowed,
+        IllegalAction::ExtraBuildNotAllowed,
+        IllegalAction::OutOfPieces,
+        IllegalAction::BlocksTeammate,
+        IllegalAction::MoveUpBlocked,
+        IllegalAction::NoBuild,
+        IllegalAction::NotInPlay,
+    ];
+}
+
+impl std::fmt::Display for IllegalAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            IllegalAction::OutOfBounds => "square is off the board",
+            IllegalAction::OccupiedDestination => "destination is occupied",
+            IllegalAction::ClimbTooHigh => "can't climb more than one level",
+            IllegalAction::NotAdjacent => "square is not adjacent to the worker",
+            IllegalAction::MoveOntoDome => "can't move onto a dome",
+            IllegalAction::BuildOnDome => "can't build on a dome",
+            IllegalAction::BuildOnDestination => "can't build where the worker is standing",
+            IllegalAction::BuildOnOccupied => "can't build on an occupied square",
+            IllegalAction::UnknownWorker => "worker is not in play",
+            IllegalAction::DomeNotAllowed => "can't build a dome there",
+            IllegalAction::ExtraBuildNotAllowed => "can't build there a second time",
+            IllegalAction::OutOfPieces => "no pieces of that kind are left",
+            IllegalAction::BlocksTeammate => "can't build a dome next to a teammate",
+            IllegalAction::MoveUpBlocked => "can't move up this turn",
+            IllegalAction::NoBuild => "nowhere to build after that move",
+            IllegalAction::NotInPlay => "the game is not at that stage",
+        })
+    }
+}
+
+impl std::error::Error for IllegalAction {}
+
+/// Why `Game::place_workers` rejected a placement
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum IllegalPlacement {
+    OutOfBounds,
+    /// Both workers were put on the same square
+    SameSquare,
+    /// Another worker is already on one of the squares
+    Occupied,
+    /// The player's workers are already on the board
+    AlreadyPlaced,
+    /// The player is out of the game, or it is past the placement phase
+    NotPlacing,
+}
+
+impl IllegalPlacement {
+    pub const ALL: [IllegalPlacement; 5] = [
+        IllegalPlacement::OutOfBounds,
+        IllegalPlacement::SameSquare,
+        IllegalPlacement::Occupied,
+        IllegalPlacement::AlreadyPlaced,
+        IllegalPlacement::NotPlacing,
+    ];
+}
+
+impl std::fmt::Display for IllegalPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            IllegalPlacement::OutOfBounds => "square is off the board",
+            IllegalPlacement::SameSquare => "both workers are on the same square",
+            IllegalPlacement::Occupied => "square is already taken",
+            IllegalPlacement::AlreadyPlaced => "workers are already placed",
+            IllegalPlacement::NotPlacing => "the game is not at that stage",
+        })
+    }
+}
+
+impl std::error::Error for IllegalPlacement {}
+
+/// Lets a recorded placement fail the same way as a recorded action when it is replayed
+impl From<IllegalPlacement> for IllegalAction {
+    fn from(placement: IllegalPlacement) -> Self {
+        match placement {
+            IllegalPlacement::OutOfBounds => IllegalAction::OutOfBounds,
+            IllegalPlacement::SameSquare | IllegalPlacement::Occupied => {
+                IllegalAction::OccupiedDestination
+            }
+            IllegalPlacement::AlreadyPlaced | IllegalPlacement::NotPlacing => {
+                IllegalAction::NotInPlay
+            }
+        }
+    }
+}
+
+/// Why a player was knocked out of the game
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum EliminationReason {
+    /// Neither worker could move and then build
+    NoLegalAction,
+    IllegalAction(IllegalAction),
+    /// Kept choosing squares the workers couldn't be placed on
+    IllegalPlacement(IllegalPlacement),
+}
+
+impl std::fmt::Display for EliminationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EliminationReason::NoLegalAction => f.write_str("no legal action"),
+            EliminationReason::IllegalAction(reason) => write!(f, "illegal action ({})", reason),
+            EliminationReason::IllegalPlacement(reason) => {
+                write!(f, "illegal placement ({})", reason)
+            }
+        }
+    }
+}
 
-pub type Action = (Worker, (u8, u8), (u8, u8));
 pub type StartLocation = ((u8, u8), (u8, u8));
 
+/// Pieces in the box, for the limited supply rule: level 1, level 2 and level 3 blocks, then domes
+pub const PIECE_SUPPLY: [u8; 4] = [22, 18, 14, 18];
+
+/// Seats at the table. Games with fewer players leave the other seats `Dead`.
+pub const MAX_PLAYERS: usize = 4;
+
+thread_local! {
+    static PLAYER_RNG: std::cell::RefCell<rand::rngs::StdRng> =
+        std::cell::RefCell::new(rand::SeedableRng::from_entropy());
+}
+
+/// Runs `f` with the random number generator players make their random choices with, so
+/// `play_game` can seed it and the game can be played again the same way
+pub fn with_rng<T>(f: impl FnOnce(&mut rand::rngs::StdRng) -> T) -> T {
+    PLAYER_RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Seeds the random number generator of `with_rng` on this thread
+pub fn seed_rng(seed: u64) {
+    PLAYER_RNG.with(|rng| *rng.borrow_mut() = rand::SeedableRng::seed_from_u64(seed));
+}
+
 pub trait Player: Send + Sync {
+    /// Only called when the player has at least one legal action
     fn get_action(&self, game: &Game, player_id: usize) -> Action;
 
     fn get_starting_position(
         &self,
         game: &Game,
-        player_locations: &[((u8, u8), (u8, u8))],
+        player_locations: &[StartLocation],
     ) -> StartLocation;
+
+    /// Shown in game records, defaults to the type name
+    fn name(&self) -> String {
+        let kind = self.kind();
+        kind[..kind.find('<').unwrap_or(kind.len())]
+            .rsplit("::")
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    fn kind(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum TowerStates {
     Empty,
     Level1,
@@ -51,16 +310,15 @@ impl TowerStates {
     }
 }
 
-impl ToString for TowerStates {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for TowerStates {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
             TowerStates::Empty => "◌",
             TowerStates::Level1 => "○",
             TowerStates::Level2 => "◍",
             TowerStates::Level3 => "◉",
             TowerStates::Capped => "●",
-        }
-        .to_string()
+        })
     }
 }
 
@@ -70,94 +328,823 @@ impl std::cmp::PartialOrd for TowerStates {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Worker {
     One,
     Two,
 }
 
-impl ToString for Worker {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Worker {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
             Worker::One => "O",
             Worker::Two => "T",
-        }
-        .to_string()
+        })
     }
 }
-#[derive(PartialEq, Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Status {
     Playing,
     Dead,
 }
 
+impl Status {
+    /// `Playing` for the first `players` seats and `Dead` for the rest
+    pub fn seats(players: usize) -> [Status; MAX_PLAYERS] {
+        let mut statuses = [Status::Dead; MAX_PLAYERS];
+        for status in statuses.iter_mut().take(players) {
+            *status = Status::Playing;
+        }
+        statuses
+    }
+}
+
+/// The rows and columns a game is played on. Boards smaller than 5x5 use the squares nearest A1
+/// of the same 5x5 storage, so squares keep their names and the rest stay empty.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "UncheckedBoardSize")]
+pub struct BoardSize {
+    /// Columns, lettered from A
+    width: u8,
+    /// Rows, numbered from 1
+    height: u8,
+}
+
+// Sizes in saved games go through `BoardSize::new` like any other
+#[derive(serde::Deserialize)]
+struct UncheckedBoardSize {
+    width: u8,
+    height: u8,
+}
+
+impl std::convert::TryFrom<UncheckedBoardSize> for BoardSize {
+    type Error = String;
+
+    fn try_from(size: UncheckedBoardSize) -> Result<Self, Self::Error> {
+        BoardSize::new(size.width, size.height).ok_or_else(|| {
+            format!(
+                "board size {}x{} is not between 3x3 and 5x5",
+                size.width, size.height
+            )
+        })
+    }
+}
+
+impl BoardSize {
+    pub const FULL: BoardSize = BoardSize {
+        width: 5,
+        height: 5,
+    };
+
+    /// A board with 3 to 5 squares each way
+    pub fn new(width: u8, height: u8) -> Option<Self> {
+        if (3..=5).contains(&width) && (3..=5).contains(&height) {
+            Some(Self { width, height })
+        } else {
+            None
+        }
+    }
+
+    pub fn width(self) -> u8 {
+        self.width
+    }
+
+    pub fn height(self) -> u8 {
+        self.height
+    }
+
+    pub fn contains(self, (x, y): (u8, u8)) -> bool {
+        x < self.height && y < self.width
+    }
+
+    pub fn is_on_perimeter(self, (x, y): (u8, u8)) -> bool {
+        x == 0 || x == self.height - 1 || y == 0 || y == self.width - 1
+    }
+
+    /// Every square of the board, row by row
+    pub fn squares(self) -> impl Iterator<Item = (u8, u8)> {
+        (0..self.height).flat_map(move |x| (0..self.width).map(move |y| (x, y)))
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        BoardSize::FULL
+    }
+}
+
+impl std::fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Every field goes into the Zobrist hash kept alongside them, so they are read through the
+/// methods of the same name and only changed through methods that keep the hash up to date.
+/// Code in this crate that writes to them directly has to call `refresh_hash` afterwards.
 #[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "UnhashedGame")]
 pub struct Game {
-    pub board: [[TowerStates; 5]; 5],
-    pub player_locations: [((u8, u8), (u8, u8)); 3],
-    pub player_statuses: [Status; 3],
+    board: [[TowerStates; 5]; 5],
+    player_locations: [((u8, u8), (u8, u8)); MAX_PLAYERS],
+    player_statuses: [Status; MAX_PLAYERS],
+    player_gods: [Option<God>; MAX_PLAYERS],
+    to_move: usize,
+    /// Pieces left of each kind, in the order of `PIECE_SUPPLY`, when playing with a limited
+    /// supply
+    #[serde(default)]
+    piece_supply: Option<[u8; 4]>,
+    /// Seats 0 and 2 play against seats 1 and 3, see `Game::are_teammates`
+    #[serde(default)]
+    teams: bool,
+    #[serde(default)]
+    effects: TurnEffects,
+    /// Squares outside of the board have to stay empty, see `BoardSize`
+    #[serde(default)]
+    size: BoardSize,
+    #[serde(skip_serializing)]
+    hash: u64,
 }
 
-impl Game {
-    pub fn can_move_to_square(&self, player_id: usize, worker: Worker, movement: (u8, u8)) -> bool {
-        let (move_x, move_y) = movement;
-        let base_worker = if worker == Worker::One {
-            self.player_locations[player_id].0
+// The hash isn't stored in saved games, so it is recalculated when loading them. Games saved
+// before there were four seats have three of each, and the rest are filled in as empty seats.
+#[derive(serde::Deserialize)]
+struct UnhashedGame {
+    board: [[TowerStates; 5]; 5],
+    player_locations: Vec<((u8, u8), (u8, u8))>,
+    player_statuses: Vec<Status>,
+    #[serde(default)]
+    player_gods: Vec<Option<God>>,
+    #[serde(default)]
+    to_move: usize,
+    #[serde(default)]
+    piece_supply: Option<[u8; 4]>,
+    #[serde(default)]
+    teams: bool,
+    #[serde(default)]
+    effects: TurnEffects,
+    #[serde(default)]
+    size: BoardSize,
+}
+
+/// The first `MAX_PLAYERS` entries of `values`, padded with `empty`
+fn seats<T: Copy>(values: &[T], empty: T) -> [T; MAX_PLAYERS] {
+    let mut result = [empty; MAX_PLAYERS];
+    for (seat, &value) in result.iter_mut().zip(values.iter()) {
+        *seat = value;
+    }
+    result
+}
+
+impl std::convert::TryFrom<UnhashedGame> for Game {
+    type Error = String;
+
+    fn try_from(game: UnhashedGame) -> Result<Self, Self::Error> {
+        // The hash is looked up by seat, so a seat past the last one can't be hashed
+        if game.to_move >= MAX_PLAYERS {
+            return Err(format!("seat {} to move does not exist", game.to_move));
+        }
+        if let Some(seat) = game.effects.moved_up {
+            if seat >= MAX_PLAYERS {
+                return Err(format!("seat {} that moved up does not exist", seat));
+            }
+        }
+        let mut result = Game::new(
+            game.board,
+            seats(&game.player_locations, ((17, 17), (17, 17))),
+            seats(&game.player_statuses, Status::Dead),
+        );
+        result.player_gods = seats(&game.player_gods, None);
+        result.to_move = game.to_move;
+        result.piece_supply = game.piece_supply;
+        result.teams = game.teams;
+        result.effects = game.effects;
+        result.size = game.size;
+        result.refresh_hash();
+        Ok(result)
+    }
+}
+
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        debug_assert_eq!(self.hash, zobrist::hash_game(self));
+        debug_assert_eq!(other.hash, zobrist::hash_game(other));
+        self.hash == other.hash
+            && self.board == other.board
+            && self.player_locations == other.player_locations
+            && self.player_statuses == other.player_statuses
+            && self.player_gods == other.player_gods
+            && self.to_move == other.to_move
+            && self.piece_supply == other.piece_supply
+            && self.teams == other.teams
+            && self.effects == other.effects
+            && self.size == other.size
+    }
+}
+
+impl Eq for Game {}
+
+impl std::hash::Hash for Game {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        debug_assert_eq!(self.hash, zobrist::hash_game(self));
+        state.write_u64(self.hash);
+    }
+}
+
+fn neighbours((x, y): (u8, u8)) -> [(u8, u8); 8] {
+    [
+        (x.wrapping_sub(1), y.wrapping_sub(1)),
+        (x, y.wrapping_sub(1)),
+        (x + 1, y.wrapping_sub(1)),
+        (x.wrapping_sub(1), y),
+        (x.wrapping_sub(1), y + 1),
+        (x, y + 1),
+        (x + 1, y),
+        (x + 1, y + 1),
+    ]
+}
+
+/// Whether the seats are on the same side. In team play the even seats are one team and the odd
+/// seats the other, otherwise every seat plays for itself.
+fn on_same_team(teams: bool, player_id: usize, other: usize) -> bool {
+    player_id == other || (teams && player_id % 2 == other % 2)
+}
+
+/// Whether the square is within the 5x5 arrays every board is stored in, see
+/// `BoardSize::contains` for whether it is on a particular board
+const fn is_in_storage((x, y): (u8, u8)) -> bool {
+    x < BoardSize::FULL.height && y < BoardSize::FULL.width
+}
+
+/// Whether `supply` has the pieces for the builds of the action, given the height of each square
+/// before building. An unlimited supply always does.
+fn has_pieces_for(
+    supply: Option<[u8; 4]>,
+    action: &Action,
+    height: impl Fn((u8, u8)) -> u8,
+) -> bool {
+    let supply = match supply {
+        Some(supply) => supply,
+        None => return true,
+    };
+    let mut needed = [0; 5];
+    let level = height(action.build);
+    needed[if action.dome { 3 } else { level as usize }] += 1;
+    if let Some(extra_build) = action.extra_build {
+        let extra_level = if extra_build == action.build {
+            level + 1
         } else {
-            self.player_locations[player_id].1
+            height(extra_build)
         };
+        needed[extra_level as usize] += 1;
+    }
+    needed
+        .iter()
+        .zip(supply.iter())
+        .all(|(needed, left)| needed <= left)
+}
 
-        move_x <= 4
-            && move_y <= 4
-            && self
-                .player_locations
+/// Squares the builds of the action put a dome on, given the height of each square before
+/// building. Hephaestus's second build on the same square never makes a dome.
+fn domed_squares(action: &Action, height: impl Fn((u8, u8)) -> u8) -> [Option<(u8, u8)>; 2] {
+    let build = if action.dome || height(action.build) == 3 {
+        Some(action.build)
+    } else {
+        None
+    };
+    let extra_build = action
+        .extra_build
+        .filter(|&extra_build| extra_build != action.build && height(extra_build) == 3);
+    [build, extra_build]
+}
+
+fn is_adjacent(a: (u8, u8), b: (u8, u8)) -> bool {
+    a != b && (a.0 as i8 - b.0 as i8).abs() <= 1 && (a.1 as i8 - b.1 as i8).abs() <= 1
+}
+
+impl Game {
+    pub fn new(
+        board: [[TowerStates; 5]; 5],
+        player_locations: [StartLocation; MAX_PLAYERS],
+        player_statuses: [Status; MAX_PLAYERS],
+    ) -> Self {
+        let mut game = Self {
+            board,
+            player_locations,
+            player_statuses,
+            player_gods: [None; MAX_PLAYERS],
+            piece_supply: None,
+            teams: false,
+            effects: TurnEffects::default(),
+            size: BoardSize::FULL,
+            to_move: player_statuses
                 .iter()
-                .enumerate()
-                .filter(|(i, _)| self.player_statuses[*i] == Status::Playing)
-                .all(|(_, (w1, w2))| movement != *w1 && movement != *w2)
-            && (self.board[base_worker.0 as usize][base_worker.1 as usize].to_int() as i8
-                - self.board[move_x as usize][move_y as usize].to_int() as i8)
-                >= -1
-            && movement != base_worker
-            && (base_worker.0 as i8 - move_x as i8).abs() <= 1
-            && (base_worker.1 as i8 - move_y as i8).abs() <= 1
-        //&& self.board[move_x as usize][move_y as usize] != TowerStates::Capped
-        // Not needed as to get onto a capped tower, would have to already be at level 3, which wins the game.
+                .position(|&status| status == Status::Playing)
+                .unwrap_or(0),
+            hash: 0,
+        };
+        game.refresh_hash();
+        game
     }
-    pub fn is_valid(
+
+    /// One line holding the whole game, see `notation::position_to_string`
+    pub fn to_position_string(&self) -> String {
+        notation::position_to_string(self)
+    }
+
+    pub fn from_position_string(position: &str) -> Result<Self, notation::PositionError> {
+        notation::parse_position(position)
+    }
+
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn refresh_hash(&mut self) {
+        self.hash = zobrist::hash_game(self);
+    }
+
+    pub fn board(&self) -> [[TowerStates; 5]; 5] {
+        self.board
+    }
+
+    pub fn player_locations(&self) -> [StartLocation; MAX_PLAYERS] {
+        self.player_locations
+    }
+
+    pub fn player_statuses(&self) -> [Status; MAX_PLAYERS] {
+        self.player_statuses
+    }
+
+    pub fn player_gods(&self) -> [Option<God>; MAX_PLAYERS] {
+        self.player_gods
+    }
+
+    pub fn set_player_gods(&mut self, player_gods: [Option<God>; MAX_PLAYERS]) {
+        self.player_gods = player_gods;
+        self.refresh_hash();
+    }
+
+    /// The player who acts next
+    pub fn to_move(&self) -> usize {
+        self.to_move
+    }
+
+    /// Pieces left of each kind, in the order of `PIECE_SUPPLY`, when playing with a limited
+    /// supply
+    pub fn piece_supply(&self) -> Option<[u8; 4]> {
+        self.piece_supply
+    }
+
+    /// Whether the game is played in teams, see `play_in_teams`
+    pub fn teams(&self) -> bool {
+        self.teams
+    }
+
+    pub fn effects(&self) -> TurnEffects {
+        self.effects
+    }
+
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
+    /// Plays with the limited supply of `PIECE_SUPPLY`, less the pieces already on the board.
+    /// Domes are counted as sitting on a complete tower.
+    pub fn limit_pieces(&mut self) {
+        let mut supply = PIECE_SUPPLY;
+        for tower in self.board.iter().flatten() {
+            for left in supply.iter_mut().take(tower.to_int() as usize) {
+                *left = left.saturating_sub(1);
+            }
+        }
+        self.piece_supply = Some(supply);
+        self.refresh_hash();
+    }
+
+    /// Plays on a smaller board, which has to be done before anything is placed outside of it
+    pub fn set_board_size(&mut self, size: BoardSize) {
+        self.size = size;
+        self.refresh_hash();
+    }
+
+    pub fn height(&self, (x, y): (u8, u8)) -> TowerStates {
+        self.board[x as usize][y as usize]
+    }
+
+    fn set_height(&mut self, square: (u8, u8), level: TowerStates) {
+        let old_level = self.height(square);
+        if let (Some(supply), true) = (&mut self.piece_supply, level != old_level) {
+            // Building takes the piece for the new level, and taking the build back returns it
+            let piece = level.to_int().max(old_level.to_int()) as usize - 1;
+            let left = if level > old_level {
+                supply[piece].saturating_sub(1)
+            } else {
+                supply[piece] + 1
+            };
+            self.hash ^=
+                zobrist::supply_key(piece, supply[piece]) ^ zobrist::supply_key(piece, left);
+            supply[piece] = left;
+        }
+        self.hash ^= zobrist::level_key(square, old_level) ^ zobrist::level_key(square, level);
+        self.board[square.0 as usize][square.1 as usize] = level;
+    }
+
+    /// The next player still playing after `player_id`, which is `player_id` itself if no one
+    /// else is left
+    pub fn next_player(&self, player_id: usize) -> usize {
+        (1..=MAX_PLAYERS)
+            .map(|offset| (player_id + offset) % MAX_PLAYERS)
+            .find(|&i| self.player_statuses[i] == Status::Playing)
+            .unwrap_or(player_id)
+    }
+
+    /// Plays seats 0 and 2 against seats 1 and 3. Teammates share a win, can't force each
+    /// other's workers around with Apollo or Minotaur, and can't dome a square next to each
+    /// other's workers.
+    pub fn play_in_teams(&mut self) {
+        self.teams = true;
+        self.refresh_hash();
+    }
+
+    /// Whether the players are on the same side, which outside of team play means they are the
+    /// same player
+    pub fn are_teammates(&self, player_id: usize, other: usize) -> bool {
+        on_same_team(self.teams, player_id, other)
+    }
+
+    /// Whether everyone still playing is on one side, which ends the game
+    pub fn one_side_left(&self) -> bool {
+        let mut playing = (0..MAX_PLAYERS).filter(|&i| self.player_statuses[i] == Status::Playing);
+        match playing.next() {
+            Some(first) => playing.all(|other| self.are_teammates(first, other)),
+            None => true,
+        }
+    }
+
+    /// Whether the player may move up, which they can't after an opposing Athena moved up
+    pub fn can_move_up(&self, player_id: usize) -> bool {
+        match self.effects.moved_up {
+            Some(athena) => {
+                self.player_statuses[athena] == Status::Dead
+                    || self.are_teammates(athena, player_id)
+            }
+            None => true,
+        }
+    }
+
+    /// Whether an opposing Hera stops the player winning on the edge of the board
+    pub fn opposed_by_hera(&self, player_id: usize) -> bool {
+        (0..MAX_PLAYERS).any(|other| {
+            self.player_gods[other] == Some(God::Hera)
+                && self.player_statuses[other] == Status::Playing
+                && !self.are_teammates(player_id, other)
+        })
+    }
+
+    fn set_effects(&mut self, effects: TurnEffects) {
+        self.hash ^= zobrist::effects_key(self.effects) ^ zobrist::effects_key(effects);
+        self.effects = effects;
+    }
+
+    fn set_to_move(&mut self, player_id: usize) {
+        self.hash ^= zobrist::KEYS.to_move[self.to_move] ^ zobrist::KEYS.to_move[player_id];
+        self.to_move = player_id;
+    }
+
+    pub fn set_status(&mut self, player_id: usize, status: Status) {
+        self.hash ^= zobrist::status_key(player_id, self.player_statuses[player_id])
+            ^ zobrist::status_key(player_id, status);
+        self.player_statuses[player_id] = status;
+        if status == Status::Dead && self.to_move == player_id {
+            self.set_to_move(self.next_player(player_id));
+        }
+    }
+
+    /// Takes the player out of the game. If the game carries on without them, their workers are
+    /// removed from the board
+    pub fn eliminate(&mut self, player_id: usize) {
+        self.set_status(player_id, Status::Dead);
+        if !self.one_side_left() {
+            self.set_worker_location(player_id, Worker::One, (17, 17));
+            self.set_worker_location(player_id, Worker::Two, (17, 17));
+        }
+    }
+
+    pub fn worker_location(&self, player_id: usize, worker: Worker) -> (u8, u8) {
+        match worker {
+            Worker::One => self.player_locations[player_id].0,
+            Worker::Two => self.player_locations[player_id].1,
+        }
+    }
+
+    pub fn set_worker_location(&mut self, player_id: usize, worker: Worker, square: (u8, u8)) {
+        self.hash ^=
+            zobrist::worker_key(player_id, worker, self.worker_location(player_id, worker))
+                ^ zobrist::worker_key(player_id, worker, square);
+        match worker {
+            Worker::One => self.player_locations[player_id].0 = square,
+            Worker::Two => self.player_locations[player_id].1 = square,
+        }
+    }
+
+    pub fn occupant(&self, square: (u8, u8)) -> Option<(usize, Worker)> {
+        self.player_locations
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.player_statuses[*i] == Status::Playing)
+            .find_map(|(i, &(w1, w2))| {
+                if w1 == square {
+                    Some((i, Worker::One))
+                } else if w2 == square {
+                    Some((i, Worker::Two))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// The squares of the board a worker can be placed on, row by row. Squares in `placed` or
+    /// with a worker on them are taken.
+    pub fn free_squares<'a>(
+        &'a self,
+        placed: &'a [StartLocation],
+    ) -> impl Iterator<Item = (u8, u8)> + 'a {
+        self.size.squares().filter(move |&square| {
+            self.occupant(square).is_none()
+                && placed.iter().all(|&(w1, w2)| w1 != square && w2 != square)
+        })
+    }
+
+    /// Every pair of free squares the player's workers can be placed on, for the first worker
+    /// and then the second. Squares in `placed` or with a worker on them are taken.
+    pub fn list_possible_start_locations(&self, placed: &[StartLocation]) -> Vec<StartLocation> {
+        let free: Vec<(u8, u8)> = self.free_squares(placed).collect();
+        free.iter()
+            .flat_map(|&w1| {
+                free.iter()
+                    .filter(move |&&w2| w2 != w1)
+                    .map(move |&w2| (w1, w2))
+            })
+            .collect()
+    }
+
+    /// Puts the player's workers on the board at the start of the game
+    pub fn place_workers(
+        &mut self,
+        player_id: usize,
+        (w1, w2): StartLocation,
+    ) -> Result<(), IllegalPlacement> {
+        if self.player_statuses[player_id] != Status::Playing {
+            return Err(IllegalPlacement::NotPlacing);
+        }
+        if self.player_locations[player_id] != ((17, 17), (17, 17)) {
+            return Err(IllegalPlacement::AlreadyPlaced);
+        }
+        if !self.size.contains(w1) || !self.size.contains(w2) {
+            return Err(IllegalPlacement::OutOfBounds);
+        }
+        if w1 == w2 {
+            return Err(IllegalPlacement::SameSquare);
+        }
+        if self.occupant(w1).is_some() || self.occupant(w2).is_some() {
+            return Err(IllegalPlacement::Occupied);
+        }
+        self.set_worker_location(player_id, Worker::One, w1);
+        self.set_worker_location(player_id, Worker::Two, w2);
+        Ok(())
+    }
+
+    /// Square a Minotaur moving from `from` to `to` would push the worker on `to` into
+    fn push_square(&self, from: (u8, u8), to: (u8, u8)) -> Option<(u8, u8)> {
+        let pushed = (
+            (2 * to.0 as i8 - from.0 as i8) as u8,
+            (2 * to.1 as i8 - from.1 as i8) as u8,
+        );
+        if self.size.contains(pushed)
+            && self.occupant(pushed).is_none()
+            && self.height(pushed) != TowerStates::Capped
+        {
+            Some(pushed)
+        } else {
+            None
+        }
+    }
+
+    fn check_step(
+        &self,
+        player_id: usize,
+        from: (u8, u8),
+        to: (u8, u8),
+    ) -> Result<(), IllegalAction> {
+        if !self.size.contains(to) {
+            Err(IllegalAction::OutOfBounds)
+        } else if !is_adjacent(from, to) {
+            Err(IllegalAction::NotAdjacent)
+        } else if self.height(to) == TowerStates::Capped {
+            Err(IllegalAction::MoveOntoDome)
+        // Forced moves from Apollo or Minotaur can leave a worker on level 3, so a dome can be
+        // only one level above it
+        } else if self.height(to).to_int() > self.height(from).to_int() + 1 {
+            Err(IllegalAction::ClimbTooHigh)
+        } else if self.height(to) > self.height(from) && !self.can_move_up(player_id) {
+            Err(IllegalAction::MoveUpBlocked)
+        } else {
+            match self.occupant(to) {
+                None => Ok(()),
+                Some((other, _)) if !self.are_teammates(player_id, other) => {
+                    match self.player_gods[player_id] {
+                        Some(God::Apollo) => Ok(()),
+                        Some(God::Minotaur) if self.push_square(from, to).is_some() => Ok(()),
+                        _ => Err(IllegalAction::OccupiedDestination),
+                    }
+                }
+                Some(_) => Err(IllegalAction::OccupiedDestination),
+            }
+        }
+    }
+
+    fn can_step(&self, player_id: usize, from: (u8, u8), to: (u8, u8)) -> bool {
+        self.check_step(player_id, from, to).is_ok()
+    }
+
+    fn is_winning_step(&self, player_id: usize, from: (u8, u8), to: (u8, u8)) -> bool {
+        let (from_height, to_height) = (self.height(from).to_int(), self.height(to).to_int());
+        ((to_height == 3 && from_height < 3)
+            || (self.player_gods[player_id] == Some(God::Pan) && from_height >= to_height + 2))
+            && !(self.size.is_on_perimeter(to) && self.opposed_by_hera(player_id))
+    }
+
+    /// Squares an Artemis worker on `from` could stop on before moving on to `to`
+    fn artemis_midpoints(
+        &self,
+        player_id: usize,
+        from: (u8, u8),
+        to: (u8, u8),
+    ) -> impl Iterator<Item = (u8, u8)> + '_ {
+        IntoIterator::into_iter(neighbours(from)).filter(move |&mid| {
+            to != from
+                && self.can_step(player_id, from, mid)
+                && !self.is_winning_step(player_id, from, mid)
+                && self.can_step(player_id, mid, to)
+        })
+    }
+
+    pub fn check_move(
         &self,
         player_id: usize,
         worker: Worker,
         movement: (u8, u8),
+    ) -> Result<(), IllegalAction> {
+        let base_worker = self.worker_location(player_id, worker);
+        if !self.size.contains(base_worker) || self.player_statuses[player_id] != Status::Playing {
+            return Err(IllegalAction::UnknownWorker);
+        }
+        let step = self.check_step(player_id, base_worker, movement);
+        if step.is_err()
+            && self.player_gods[player_id] == Some(God::Artemis)
+            && self.size.contains(movement)
+            && self
+                .artemis_midpoints(player_id, base_worker, movement)
+                .next()
+                .is_some()
+        {
+            return Ok(());
+        }
+        step
+    }
+
+    pub fn can_move_to_square(&self, player_id: usize, worker: Worker, movement: (u8, u8)) -> bool {
+        self.check_move(player_id, worker, movement).is_ok()
+    }
+
+    pub fn is_winning_move(&self, player_id: usize, worker: Worker, movement: (u8, u8)) -> bool {
+        let base_worker = self.worker_location(player_id, worker);
+        (self.can_step(player_id, base_worker, movement)
+            && self.is_winning_step(player_id, base_worker, movement))
+            || (self.player_gods[player_id] == Some(God::Artemis)
+                && self
+                    .artemis_midpoints(player_id, base_worker, movement)
+                    .any(|mid| self.is_winning_step(player_id, mid, movement)))
+    }
+
+    /// Moves the worker, along with any opponent worker forced out of the way by Apollo or
+    /// Minotaur, which is returned. The move must already have been checked with `check_move`.
+    fn move_worker(
+        &mut self,
+        player_id: usize,
+        worker: Worker,
+        movement: (u8, u8),
+    ) -> Option<(usize, Worker)> {
+        let base_worker = self.worker_location(player_id, worker);
+        let displaced = self.occupant(movement);
+        if let Some((other, other_worker)) = displaced {
+            let forced = match self.player_gods[player_id] {
+                Some(God::Minotaur) => self.push_square(base_worker, movement).unwrap(),
+                _ => base_worker,
+            };
+            self.set_worker_location(other, other_worker, forced);
+        }
+        self.set_worker_location(player_id, worker, movement);
+        displaced
+    }
+
+    fn check_build_on_square(
+        &self,
+        worker_location: (u8, u8),
         build: (u8, u8),
-        checked_movement: bool,
-    ) -> bool {
-        let (move_x, move_y) = movement;
-        let (build_x, build_y) = build;
+    ) -> Result<(), IllegalAction> {
+        if !self.size.contains(build) {
+            Err(IllegalAction::OutOfBounds)
+        } else if build == worker_location {
+            Err(IllegalAction::BuildOnDestination)
+        } else if !is_adjacent(worker_location, build) {
+            Err(IllegalAction::NotAdjacent)
+        } else if self.occupant(build).is_some() {
+            Err(IllegalAction::BuildOnOccupied)
+        } else if self.height(build) == TowerStates::Capped {
+            Err(IllegalAction::BuildOnDome)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether one of the action's domes would go next to a teammate's worker in team play
+    fn blocks_teammate(&self, player_id: usize, action: &Action) -> bool {
+        self.teams
+            && domed_squares(action, |square| self.height(square).to_int())
+                .iter()
+                .flatten()
+                .any(|&dome| {
+                    (0..MAX_PLAYERS)
+                        .filter(|&other| {
+                            other != player_id
+                                && self.are_teammates(player_id, other)
+                                && self.player_statuses[other] == Status::Playing
+                        })
+                        .any(|other| {
+                            let (w1, w2) = self.player_locations[other];
+                            is_adjacent(dome, w1) || is_adjacent(dome, w2)
+                        })
+                })
+    }
 
-        let (old_w1, old_w2) = self.player_locations[player_id];
+    /// Checks the builds of `action`, on a game where its move has already been made
+    fn check_builds(&self, player_id: usize, action: &Action) -> Result<(), IllegalAction> {
+        let god = self.player_gods[player_id];
+        self.check_build_on_square(action.movement, action.build)?;
+        // A normal build on level 3 is already a dome
+        if action.dome
+            && (god != Some(God::Atlas) || self.height(action.build) == TowerStates::Level3)
+        {
+            return Err(IllegalAction::DomeNotAllowed);
+        }
+        match (action.extra_build, god) {
+            (None, _) => Ok(()),
+            (Some(extra_build), Some(God::Demeter)) if extra_build != action.build => {
+                self.check_build_on_square(action.movement, extra_build)
+            }
+            (Some(extra_build), Some(God::Hephaestus))
+                if extra_build == action.build
+                    && !action.dome
+                    && self.height(action.build).to_int() <= 1 =>
+            {
+                Ok(())
+            }
+            _ => Err(IllegalAction::ExtraBuildNotAllowed),
+        }?;
+        if self.blocks_teammate(player_id, action) {
+            return Err(IllegalAction::BlocksTeammate);
+        }
+        if has_pieces_for(self.piece_supply, action, |square| {
+            self.height(square).to_int()
+        }) {
+            Ok(())
+        } else {
+            Err(IllegalAction::OutOfPieces)
+        }
+    }
 
-        let can_move_to_square =
-            checked_movement || self.can_move_to_square(player_id, worker, movement);
-        if self.board[move_x as usize][move_y as usize] == TowerStates::Level3 {
-            return can_move_to_square;
+    pub fn is_valid(
+        &self,
+        player_id: usize,
+        action: Action,
+        checked_movement: bool,
+    ) -> Result<(), IllegalAction> {
+        // A turn that was started with apply_move has to be finished with apply_build
+        if self.effects.to_build.is_some() {
+            return Err(IllegalAction::NotInPlay);
+        }
+        if !checked_movement {
+            self.check_move(player_id, action.worker, action.movement)?;
+        }
+        if self.is_winning_move(player_id, action.worker, action.movement) {
+            return Ok(());
             // If will win by moving, then don't check build
         }
-        can_move_to_square
-            && build_x <= 4
-            && build_y <= 4
-            && ((worker == Worker::One && build != old_w2)
-                || (worker == Worker::Two && build != old_w1))
-            && self
-                .player_locations
-                .iter()
-                .enumerate()
-                .filter(|(i, _)| self.player_statuses[*i] == Status::Playing && *i != player_id)
-                .all(|(_, (w1, w2))| (build != *w1 && build != *w2))
-            && movement != build
-            && (move_x as i8 - build_x as i8).abs() <= 1
-            && (move_y as i8 - build_y as i8).abs() <= 1
-            && self.board[build_x as usize][build_y as usize] != TowerStates::Capped
+        let mut moved = *self;
+        moved.move_worker(player_id, action.worker, action.movement);
+        moved.check_builds(player_id, &action)
     }
 
     pub fn print_board(&self) {
@@ -165,20 +1152,34 @@ impl Game {
         let mut new_board: [[Option<(Worker, usize)>; 5]; 5] = [[None; 5]; 5];
         for (player, &((w1x, w1y), (w2x, w2y))) in self.player_locations.iter().enumerate() {
             if self.player_statuses[player] == Status::Playing
-                && (w1x <= 4 && w1y <= 4 && w2x <= 4 && w2y <= 4)
+                && self.size.contains((w1x, w1y))
+                && self.size.contains((w2x, w2y))
             {
                 new_board[w1x as usize][w1y as usize] = Some((Worker::One, player));
                 new_board[w2x as usize][w2y as usize] = Some((Worker::Two, player));
             }
         }
+        for (player, god) in self.player_gods.iter().enumerate() {
+            if let (Some(god), Status::Playing) = (god, self.player_statuses[player]) {
+                result.push_str(&format!("Player {}: {}\n", player, god));
+            }
+        }
         result.push(' ');
-        for i in 0..5 {
+        for i in 0..self.size.width {
             result.push_str("  ");
-            result.push_str(&i.to_string());
+            result.push((b'A' + i) as char);
             result.push_str("   ");
         }
         result.push('\n');
-        for (i, (row1, row2)) in self.board.iter().zip(new_board.iter()).enumerate() {
+        let (width, height) = (self.size.width as usize, self.size.height as usize);
+        for (i, (row1, row2)) in self
+            .board
+            .iter()
+            .zip(new_board.iter())
+            .enumerate()
+            .take(height)
+        {
+            let (row1, row2) = (&row1[..width], &row2[..width]);
             result.push(' ');
             for square in row1.iter() {
                 result.push(' ');
@@ -189,7 +1190,7 @@ impl Game {
                 result.push(' ');
             }
             result.push('\n');
-            result.push_str(&i.to_string());
+            result.push_str(&(i + 1).to_string());
             for (s1, s2) in row1.iter().zip(row2.iter()) {
                 result.push(' ');
                 result.push_str(&s1.to_string());
@@ -216,51 +1217,36 @@ impl Game {
             result.push('\n');
             result.push('\n');
         }
-        println!("Levels: ◌○◍◉●\nGame:\n{}", result);
+        if let Some([level1, level2, level3, domes]) = self.piece_supply {
+            result.push_str(&format!(
+                "Pieces left: {} level 1, {} level 2, {} level 3, {} domes\n",
+                level1, level2, level3, domes
+            ));
+        }
+        println!(
+            "Levels: ◌○◍◉●\nGame:\n{}Position: {}",
+            result,
+            self.to_position_string()
+        );
     }
     pub fn list_possible_actions(&self, player_id: usize) -> Vec<Action> {
-        let mut possible_actions: Vec<Action> = Vec::with_capacity(2 * 8 * 8);
-        let (worker1, worker2) = self.player_locations[player_id];
-        for (&worker, &w) in [Worker::One, Worker::Two]
-            .iter()
-            .zip([worker1, worker2].iter())
-        {
-            for &m in [
-                (w.0.wrapping_sub(1), w.1.wrapping_sub(1)),
-                (w.0, w.1.wrapping_sub(1)),
-                (w.0 + 1, w.1.wrapping_sub(1)),
-                (w.0.wrapping_sub(1), w.1),
-                (w.0.wrapping_sub(1), w.1 + 1),
-                (w.0, w.1 + 1),
-                (w.0 + 1, w.1),
-                (w.0 + 1, w.1 + 1),
-            ]
-            .iter()
-            .filter(|m| self.can_move_to_square(player_id, worker, **m))
-            {
-                for &b in &[
-                    (m.0.wrapping_sub(1), m.1.wrapping_sub(1)),
-                    (m.0, m.1.wrapping_sub(1)),
-                    (m.0 + 1, m.1.wrapping_sub(1)),
-                    (m.0.wrapping_sub(1), m.1),
-                    (m.0.wrapping_sub(1), m.1 + 1),
-                    (m.0, m.1 + 1),
-                    (m.0 + 1, m.1),
-                    (m.0 + 1, m.1 + 1),
-                ] {
-                    if b.0 < 5 && b.1 < 5 && self.is_valid(player_id, worker, m, b, true) {
-                        possible_actions.push((worker, m, b));
-                    }
-                }
-            }
-        }
-        possible_actions
+        bitboard::BitBoard::from(*self).list_possible_actions(player_id)
+    }
+    /// Iterates over the same actions as `list_possible_actions` without allocating
+    pub fn possible_actions(&self, player_id: usize) -> bitboard::Actions {
+        bitboard::BitBoard::from(*self).possible_actions(player_id)
+    }
+    /// Each worker and square it can move to, for scoring moves before their builds
+    pub fn possible_moves(&self, player_id: usize) -> bitboard::Moves {
+        bitboard::BitBoard::from(*self).possible_moves(player_id)
     }
     pub fn is_near_player(&self, player_id: usize, pos: (u8, u8)) -> bool {
         self.player_locations
             .iter()
             .enumerate()
-            .filter(|(i, _)| self.player_statuses[*i] == Status::Playing && *i != player_id)
+            .filter(|(i, _)| {
+                self.player_statuses[*i] == Status::Playing && !self.are_teammates(player_id, *i)
+            })
             .any(|(_, (w1, w2))| {
                 ((w1.0 as i8 - pos.0 as i8).abs() <= 1 && (w1.1 as i8 - pos.1 as i8).abs() <= 1)
                     || ((w2.0 as i8 - pos.0 as i8).abs() <= 1
@@ -270,22 +1256,16 @@ impl Game {
 
     pub fn can_win_on_next_turn(&self, player_id: usize) -> bool {
         let (w1, w2) = self.player_locations[player_id];
-        for &(wx, wy) in &[w1, w2] {
-            if wx >= 5 || wy >= 5 {
+        for &worker in &[w1, w2] {
+            if !self.size.contains(worker) {
                 return false;
             }
-            if self.board[wx as usize][wy as usize] == TowerStates::Level2 {
-                for &mx in &[wx.saturating_sub(1), wx, wx + 1] {
-                    if mx < 5 {
-                        for &my in &[wy.saturating_sub(1), wy, wy + 1] {
-                            if my < 5 {
-                                if self.board[mx as usize][my as usize] == TowerStates::Level3 {
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                }
+            if self.height(worker) == TowerStates::Level2
+                && neighbours(worker).iter().any(|&square| {
+                    self.size.contains(square) && self.height(square) == TowerStates::Level3
+                })
+            {
+                return true;
             }
         }
         false
@@ -294,137 +1274,280 @@ impl Game {
     pub fn apply_action(
         &mut self,
         player_id: usize,
-        (worker, (move_x, move_y), (build_x, build_y)): Action,
+        action: Action,
         checked_is_valid: bool,
-    ) -> Result<bool, ()> {
-        if checked_is_valid
-            || self.is_valid(
-                player_id,
-                worker,
-                (move_x, move_y),
-                (build_x, build_y),
-                false,
-            )
-        {
-            if worker == Worker::One {
-                self.player_locations[player_id].0 = (move_x, move_y);
+    ) -> Result<Undo, IllegalAction> {
+        if !checked_is_valid {
+            self.is_valid(player_id, action, false)?;
+        }
+        let mut undo = Undo {
+            player_id,
+            worker: action.worker,
+            old_location: self.worker_location(player_id, action.worker),
+            displaced: None,
+            builds: [None; 2],
+            to_move: self.to_move,
+            effects: self.effects,
+            won: self.is_winning_move(player_id, action.worker, action.movement),
+        };
+        undo.displaced = self
+            .make_move(player_id, action.worker, action.movement)
+            .map(|(other, other_worker)| (other, other_worker, action.movement));
+        if undo.won {
+            return Ok(undo);
+        }
+        undo.builds = self.make_builds(&action);
+        self.set_to_move(self.next_player(player_id));
+        Ok(undo)
+    }
+
+    /// Moves the worker like `move_worker`, and keeps track of the effects the move has on later
+    /// turns
+    fn make_move(
+        &mut self,
+        player_id: usize,
+        worker: Worker,
+        movement: (u8, u8),
+    ) -> Option<(usize, Worker)> {
+        let from = self.worker_location(player_id, worker);
+        let displaced = self.move_worker(player_id, worker, movement);
+        // Athena's effect lasts from one of her turns to the next
+        if self.player_gods[player_id] == Some(God::Athena) {
+            let moved_up = self.height(movement) > self.height(from);
+            self.set_effects(TurnEffects {
+                moved_up: if moved_up { Some(player_id) } else { None },
+                ..self.effects
+            });
+        }
+        displaced
+    }
+
+    /// Makes the builds of an action already checked with `check_builds`, returning the squares
+    /// built on with the level each was at beforehand
+    fn make_builds(&mut self, action: &Action) -> [Option<((u8, u8), TowerStates)>; 2] {
+        let mut builds = [None; 2];
+        builds[0] = Some((action.build, self.height(action.build)));
+        // Since the builds were checked, they must be able to increase
+        self.set_height(
+            action.build,
+            if action.dome {
+                TowerStates::Capped
             } else {
-                self.player_locations[player_id].1 = (move_x, move_y);
-            }
-            if self.board[move_x as usize][move_y as usize] == TowerStates::Level3 {
-                return Ok(true);
+                self.height(action.build).increase().unwrap()
+            },
+        );
+        if let Some(extra_build) = action.extra_build {
+            builds[1] = Some((extra_build, self.height(extra_build)));
+            self.set_height(extra_build, self.height(extra_build).increase().unwrap());
+        }
+        builds
+    }
+
+    /// Each worker and square it can move to that `list_possible_actions` has an action for, in
+    /// the same order. These are the moves `apply_move` accepts from the player to move.
+    pub fn legal_moves(&self, player_id: usize) -> Vec<(Worker, (u8, u8))> {
+        let mut moves: Vec<(Worker, (u8, u8))> = self
+            .possible_actions(player_id)
+            .map(|action| (action.worker, action.movement))
+            .collect();
+        moves.dedup();
+        moves
+    }
+
+    /// Plays the move half of a turn for the player to move, returning whether it won. Unless
+    /// it did, the worker then has to build with `apply_build`, and until then the player has
+    /// no other moves or actions.
+    pub fn apply_move(
+        &mut self,
+        player_id: usize,
+        worker: Worker,
+        movement: (u8, u8),
+    ) -> Result<bool, IllegalAction> {
+        if player_id != self.to_move || self.effects.to_build.is_some() {
+            return Err(IllegalAction::NotInPlay);
+        }
+        self.check_move(player_id, worker, movement)?;
+        let won = self.is_winning_move(player_id, worker, movement);
+        let mut moved = *self;
+        moved.make_move(player_id, worker, movement);
+        if !won {
+            moved.set_effects(TurnEffects {
+                to_build: Some(worker),
+                ..moved.effects
+            });
+            if moved.legal_builds(player_id, worker).is_empty() {
+                return Err(IllegalAction::NoBuild);
             }
-            self.board[build_x as usize][build_y as usize] = self.board[build_x as usize]
-                [build_y as usize]
-                .increase()
-                .unwrap();
-            // Since is_valid returned true, then it must be able to increase
-            Ok(false)
-        } else {
-            Err(())
+        }
+        *self = moved;
+        Ok(won)
+    }
+
+    /// The builds the worker can make after `apply_move`, which together with its move are the
+    /// actions `list_possible_actions` has for that move
+    pub fn legal_builds(&self, player_id: usize, worker: Worker) -> Vec<Build> {
+        if player_id != self.to_move || self.effects.to_build != Some(worker) {
+            return Vec::new();
+        }
+        let location = self.worker_location(player_id, worker);
+        let squares: Vec<(u8, u8)> = self
+            .size
+            .squares()
+            .filter(|&square| is_adjacent(location, square))
+            .collect();
+        let mut builds = Vec::new();
+        for &square in squares.iter() {
+            let build = Build {
+                build: square,
+                extra_build: None,
+                dome: false,
+            };
+            let extra_builds = squares.iter().map(|&extra_build| Build {
+                extra_build: Some(extra_build),
+                ..build
+            });
+            builds.extend(
+                [
+                    build,
+                    Build {
+                        dome: true,
+                        ..build
+                    },
+                ]
+                .iter()
+                .copied()
+                .chain(extra_builds)
+                .filter(|build| {
+                    let action = Action::with_build(worker, location, *build);
+                    self.check_builds(player_id, &action).is_ok()
+                }),
+            );
+        }
+        builds
+    }
+
+    /// Plays the build half of a turn started with `apply_move`, passing the turn on
+    pub fn apply_build(&mut self, player_id: usize, build: Build) -> Result<(), IllegalAction> {
+        let worker = match self.effects.to_build {
+            Some(worker) if player_id == self.to_move => worker,
+            _ => return Err(IllegalAction::NotInPlay),
+        };
+        let action = Action::with_build(worker, self.worker_location(player_id, worker), build);
+        self.check_builds(player_id, &action)?;
+        self.make_builds(&action);
+        self.set_effects(TurnEffects {
+            to_build: None,
+            ..self.effects
+        });
+        self.set_to_move(self.next_player(player_id));
+        Ok(())
+    }
+
+    /// Takes back the action `undo` was returned for, which must be the last one applied
+    pub fn undo_action(&mut self, undo: Undo) {
+        for &(square, level) in undo.builds.iter().rev().flatten() {
+            self.set_height(square, level);
+        }
+        self.set_to_move(undo.to_move);
+        self.set_effects(undo.effects);
+        self.set_worker_location(undo.player_id, undo.worker, undo.old_location);
+        if let Some((other, other_worker, square)) = undo.displaced {
+            self.set_worker_location(other, other_worker, square);
         }
     }
 }
 
+/// Everything `Game::undo_action` needs to take back an action
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Undo {
+    pub player_id: usize,
+    pub worker: Worker,
+    pub old_location: (u8, u8),
+    // Opponent worker moved by Apollo or Minotaur, and the square it was moved from
+    pub displaced: Option<(usize, Worker, (u8, u8))>,
+    // Squares built on, with the level each was at beforehand
+    pub builds: [Option<((u8, u8), TowerStates)>; 2],
+    pub to_move: usize,
+    pub effects: TurnEffects,
+    pub won: bool,
+}
+
 pub fn main_loop(
-    player_controls: [Option<&dyn Player>; 3],
-    print_board: bool,
-    record_moves: &mut Option<[Vec<(Game, Action)>; 3]>,
-    record_start: &mut Option<[Option<(Game, Vec<((u8, u8), (u8, u8))>)>; 3]>,
-) -> usize {
-    let mut game = Game {
-        board: [[TowerStates::Empty; 5]; 5],
-        player_locations: [((17, 17), (17, 17)); 3],
-        player_statuses: {
-            let mut player_statuses = [Status::Dead; 3];
-            for (i, player) in player_controls.iter().enumerate() {
-                if player.is_some() {
-                    player_statuses[i] = Status::Playing;
-                }
-            }
-            player_statuses
-        },
-    };
-    let mut start_locations: Vec<((u8, u8), (u8, u8))> = Vec::new();
-    let players: Vec<usize> = game
-        .player_statuses
-        .iter()
-        .enumerate()
-        .filter(|(_, &status)| status == Status::Playing)
-        .map(|(i, _)| i)
-        .collect();
-    for &player_id in players.iter() {
-        if let Some(player) = &player_controls[player_id] {
-            loop {
-                let (w1, w2) = player.get_starting_position(&game, &start_locations);
-                if let Some(record_start) = record_start {
-                    record_start[player_id] = Some((game.clone(), start_locations.clone()));
-                }
-                if w1 != w2
-                    && start_locations
-                        .iter()
-                        .all(|&(val1, val2)| !(w1 == val1 || w2 == val2 || w1 == val2))
-                    && w1.0 <= 4
-                    && w1.1 <= 4
-                    && w2.0 <= 4
-                    && w2.1 <= 4
-                {
-                    start_locations.push((w1, w2));
-                    game.player_locations[player_id] = (w1, w2);
-                    break;
-                } else {
-                    println!("Failed to enter valid start location: ({:?}, {:?})", w1, w2);
-                }
-            }
+    player_controls: [Option<&dyn Player>; MAX_PLAYERS],
+    player_gods: [Option<God>; MAX_PLAYERS],
+    observers: &mut [&mut dyn Observer],
+) -> GameRecord {
+    let mut player_statuses = [Status::Dead; MAX_PLAYERS];
+    for (i, player) in player_controls.iter().enumerate() {
+        if player.is_some() {
+            player_statuses[i] = Status::Playing;
         }
     }
+    play_game(
+        GameState::new(player_statuses, player_gods),
+        player_controls,
+        observers,
+    )
+}
 
-    loop {
-        let players: Vec<usize> = game
-            .player_statuses
-            .iter()
-            .enumerate()
-            .filter(|(_, &status)| status == Status::Playing)
-            .map(|(i, _)| i)
-            .collect();
-        for &player_id in players.iter() {
-            if let Some(player) = &player_controls[player_id] {
-                if print_board {
-                    game.print_board();
-                }
-                let (worker, (move_x, move_y), (build_x, build_y)) =
-                    player.get_action(&game, player_id);
-                if let Ok(result) = game.apply_action(
-                    player_id,
-                    (worker, (move_x, move_y), (build_x, build_y)),
-                    false,
-                ) {
-                    if result {
-                        return player_id;
+/// Plays out a game set up beforehand, e.g. with `GameState::play_in_teams`. The seats with a
+/// player should be the ones playing in `state`. The players' random choices are seeded with
+/// `GameState::seed`, or a random seed that ends up in the record if it isn't set.
+pub fn play_game(
+    mut state: GameState,
+    player_controls: [Option<&dyn Player>; MAX_PLAYERS],
+    observers: &mut [&mut dyn Observer],
+) -> GameRecord {
+    let seed = state.seed().unwrap_or_else(rand::random);
+    state.set_seed(seed);
+    seed_rng(seed);
+    let mut session = GameSession::new(state, player_controls);
+    while !session.is_finished() {
+        let game = *session.state().game();
+        for event in session.step() {
+            let current_game = session.state().game();
+            for observer in observers.iter_mut() {
+                match event {
+                    Event::TurnStarted {
+                        player_id,
+                        phase: Phase::Play,
+                    } => observer.on_turn_start(current_game, player_id),
+                    Event::WorkersPlaced {
+                        player_id,
+                        start_location,
+                    } => observer.on_start_placed(&game, player_id, start_location),
+                    Event::ActionApplied { player_id, action } => {
+                        observer.on_action(&game, player_id, action)
                     }
-                } else {
-                    //println!("Not a valid move");
-                    game.player_statuses[player_id] = Status::Dead;
-                    if game
-                        .player_statuses
-                        .iter()
-                        .filter(|&status| *status == Status::Playing)
-                        .map(|_| 1)
-                        .sum::<usize>()
-                        == 1
-                    {
-                        return game
-                            .player_statuses
-                            .iter()
-                            .enumerate()
-                            .find(|(_, &status)| status == Status::Playing)
-                            .unwrap()
-                            .0;
+                    Event::ActionRejected {
+                        player_id,
+                        action,
+                        reason,
+                    } => observer.on_action_rejected(&game, player_id, action, reason),
+                    Event::PlayerEliminated(elimination) => {
+                        observer.on_elimination(current_game, &elimination)
                     }
+                    Event::GameWon { player_id } => observer.on_game_over(current_game, player_id),
+                    _ => {}
                 }
             }
+            if let Event::PlacementRejected {
+                start_location: (w1, w2),
+                reason,
+                ..
+            } = event
+            {
+                println!(
+                    "Failed to enter valid start location: ({:?}, {:?}), {}",
+                    w1, w2, reason
+                );
+            }
         }
     }
+    GameRecord::new(
+        session.state(),
+        player_controls.map(|player| player.map(record::PlayerInfo::new)),
+    )
 }
 
 #[cfg(test)]
@@ -433,42 +1556,689 @@ mod tests {
 
     #[test]
     fn single_worker_has_64_actions() {
-        let game = Game {
-            board: [[TowerStates::Empty; 5]; 5],
-            player_locations: [
+        let game =
+            Game::from_position_string("00000/00000/00000/00000/00000 C3,-/-,-/-,- PDD 0").unwrap();
+        assert_eq!(game.list_possible_actions(0).len(), 64);
+    }
+    #[test]
+    fn limited_supply_refuses_builds_without_pieces() {
+        let mut game =
+            Game::from_position_string("00000/00000/00000/00000/00000 C3,-/-,-/-,- PDD 0").unwrap();
+        assert_eq!(game.list_possible_actions(0).len(), 64);
+        game.limit_pieces();
+        assert_eq!(game.piece_supply, Some(PIECE_SUPPLY));
+        let action = Action::new(Worker::One, (2, 3), (2, 2));
+        let undo = game.apply_action(0, action, false).unwrap();
+        assert_eq!(game.piece_supply, Some([21, 18, 14, 18]));
+        game.undo_action(undo);
+        assert_eq!(game.piece_supply, Some(PIECE_SUPPLY));
+
+        let mut game = Game::from_position_string(
+            "11111/11111/11000/11111/11111 C3,-/-,-/-,- PDD 0 Hephaestus,-,- 0,1,0,18",
+        )
+        .unwrap();
+        assert_eq!(
+            game.is_valid(0, action, false),
+            Err(IllegalAction::OutOfPieces)
+        );
+        let action = Action::new(Worker::One, (2, 3), (1, 3));
+        let double = Action {
+            extra_build: Some((1, 3)),
+            ..action
+        };
+        assert_eq!(
+            game.is_valid(0, double, false),
+            Err(IllegalAction::OutOfPieces)
+        );
+        let actions = game.list_possible_actions(0);
+        assert!(actions.contains(&action) && !actions.contains(&double));
+        game.apply_action(0, action, false).unwrap();
+        assert_eq!(game.piece_supply, Some([0, 0, 0, 18]));
+        // Every square around the worker needs a level 1 or level 2 piece
+        assert!(game.list_possible_actions(0).is_empty());
+    }
+
+    #[test]
+    fn placements_are_validated() {
+        let mut game = Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            [((17, 17), (17, 17)); MAX_PLAYERS],
+            Status::seats(2),
+        );
+        assert_eq!(game.list_possible_start_locations(&[]).len(), 25 * 24);
+        game.place_workers(0, ((0, 0), (0, 1))).unwrap();
+        let cases = [
+            (((0, 0), (0, 1)), 0, IllegalPlacement::AlreadyPlaced),
+            (((0, 0), (0, 1)), 2, IllegalPlacement::NotPlacing),
+            (((0, 5), (0, 2)), 1, IllegalPlacement::OutOfBounds),
+            (((0, 2), (0, 2)), 1, IllegalPlacement::SameSquare),
+            (((0, 2), (0, 0)), 1, IllegalPlacement::Occupied),
+            (((0, 1), (0, 2)), 1, IllegalPlacement::Occupied),
+        ];
+        for &(start_location, player_id, error) in cases.iter() {
+            assert_eq!(game.place_workers(player_id, start_location), Err(error));
+        }
+        let start_locations = game.list_possible_start_locations(&[((0, 0), (0, 1))]);
+        assert_eq!(start_locations.len(), 23 * 22);
+        assert_eq!(start_locations[0], ((0, 2), (0, 3)));
+        assert_eq!(game.free_squares(&[((0, 0), (0, 1))]).count(), 23);
+
+        game.set_board_size(BoardSize::new(3, 4).unwrap());
+        assert_eq!(game.list_possible_start_locations(&[]).len(), 10 * 9);
+        assert_eq!(
+            game.place_workers(1, ((0, 2), (0, 3))),
+            Err(IllegalPlacement::OutOfBounds)
+        );
+        assert_eq!(
+            game.place_workers(1, ((4, 0), (3, 1))),
+            Err(IllegalPlacement::OutOfBounds)
+        );
+        game.place_workers(1, ((2, 2), (3, 0))).unwrap();
+    }
+    #[test]
+    fn loading_checks_the_seat_to_move() {
+        let game =
+            Game::from_position_string("00000/00000/00000/00000/00000 A1,B2/C3,D4/-,- PPD 1")
+                .unwrap();
+        let text = serde_json::to_string(&game).unwrap();
+        assert_eq!(serde_json::from_str::<Game>(&text).unwrap(), game);
+        let text = text.replace(r#""to_move":1"#, r#""to_move":7"#);
+        assert!(serde_json::from_str::<Game>(&text).is_err());
+    }
+    #[test]
+    fn loading_checks_the_seat_that_moved_up() {
+        let position = "00000/00000/00000/00000/00000 A1,B2/C3,D4/-,- PPD 1 moved-up:0";
+        let game = Game::from_position_string(position).unwrap();
+        let text = serde_json::to_string(&game).unwrap();
+        assert_eq!(serde_json::from_str::<Game>(&text).unwrap(), game);
+        let text = text.replace(r#""moved_up":0"#, r#""moved_up":7"#);
+        assert!(serde_json::from_str::<Game>(&text).is_err());
+    }
+    #[test]
+    fn loading_checks_the_board_size() {
+        let size: BoardSize = serde_json::from_str(r#"{"width":3,"height":4}"#).unwrap();
+        assert_eq!(size, BoardSize::new(3, 4).unwrap());
+        for text in [
+            r#"{"width":7,"height":5}"#,
+            r#"{"width":0,"height":5}"#,
+            r#"{"width":5,"height":2}"#,
+        ]
+        .iter()
+        {
+            assert!(serde_json::from_str::<BoardSize>(text).is_err());
+        }
+    }
+    #[test]
+    fn is_near_player_returns_false_when_not() {
+        let game = Game::from_position_string("00000/00000/00000/00000/00000 C3,D3/-,-/-,- PDD 0")
+            .unwrap();
+        assert!(!game.is_near_player(0, (3, 4)));
+    }
+    #[test]
+    fn is_near_player_returns_true_when_near_player() {
+        let game =
+            Game::from_position_string("00000/00000/00000/00000/00000 C3,D3/C5,D5/-,- PPD 0")
+                .unwrap();
+        assert!(game.is_near_player(0, (3, 4)));
+    }
+    #[test]
+    fn is_near_player_returns_false_when_only_near_player_is_dead() {
+        let game =
+            Game::from_position_string("00000/00000/00000/00000/00000 C3,D3/C5,D5/-,- PDD 0")
+                .unwrap();
+        assert!(!game.is_near_player(0, (3, 4)));
+    }
+
+    #[test]
+    fn teammates_can_not_displace_or_dome_in_each_other() {
+        let mut game = Game::from_position_string(
+            "00000/00000/00000/00030/00000 C3,A1/C2,E5/D3,A5/E1,C5 PPPP 0 Apollo,-,-,- teams",
+        )
+        .unwrap();
+        assert!(game.are_teammates(0, 2) && !game.are_teammates(0, 3));
+        assert!(!game.can_move_to_square(0, Worker::One, (2, 3)));
+        assert!(game.can_move_to_square(0, Worker::One, (1, 2)));
+        let dome = Action::new(Worker::One, (3, 2), (3, 3));
+        assert_eq!(
+            game.is_valid(0, dome, false),
+            Err(IllegalAction::BlocksTeammate)
+        );
+        assert!(!game.list_possible_actions(0).contains(&dome));
+        assert!(!game.is_near_player(0, (2, 4)));
+
+        game.teams = false;
+        game.refresh_hash();
+        assert_eq!(game.is_valid(0, dome, false), Ok(()));
+        assert!(game.can_move_to_square(0, Worker::One, (2, 3)));
+        assert!(game.is_near_player(0, (2, 4)));
+    }
+
+    fn game_with_gods(
+        player_locations: [StartLocation; MAX_PLAYERS],
+        player_gods: [Option<God>; MAX_PLAYERS],
+    ) -> Game {
+        let mut game = Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            player_locations,
+            Status::seats(2),
+        );
+        game.set_player_gods(player_gods);
+        game
+    }
+    #[test]
+    fn illegal_actions_give_a_reason() {
+        let mut game = game_with_gods(
+            [
+                ((0, 0), (2, 2)),
+                ((2, 3), (4, 4)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [None; MAX_PLAYERS],
+        );
+        game.set_height((1, 1), TowerStates::Level2);
+        game.set_height((1, 2), TowerStates::Capped);
+        let cases = [
+            (
+                Action::new(Worker::One, (0, 5), (0, 0)),
+                IllegalAction::OutOfBounds,
+            ),
+            (
+                Action::new(Worker::Two, (2, 3), (2, 2)),
+                IllegalAction::OccupiedDestination,
+            ),
+            (
+                Action::new(Worker::One, (1, 1), (0, 0)),
+                IllegalAction::ClimbTooHigh,
+            ),
+            (
+                Action::new(Worker::One, (2, 0), (1, 0)),
+                IllegalAction::NotAdjacent,
+            ),
+            (
+                Action::new(Worker::Two, (1, 2), (2, 2)),
+                IllegalAction::MoveOntoDome,
+            ),
+            (
+                Action::new(Worker::Two, (2, 1), (1, 2)),
+                IllegalAction::BuildOnDome,
+            ),
+            (
+                Action::new(Worker::Two, (2, 1), (2, 1)),
+                IllegalAction::BuildOnDestination,
+            ),
+            (
+                Action::new(Worker::One, (1, 0), (2, 0)),
+                IllegalAction::BuildOnOccupied,
+            ),
+        ];
+        game.set_worker_location(1, Worker::Two, (2, 0));
+        for &(action, reason) in cases.iter() {
+            assert_eq!(game.is_valid(0, action, false), Err(reason), "{:?}", action);
+            assert_eq!(game.apply_action(0, action, false), Err(reason));
+        }
+        game.set_status(0, Status::Dead);
+        assert_eq!(
+            game.is_valid(0, Action::new(Worker::One, (1, 0), (0, 0)), false),
+            Err(IllegalAction::UnknownWorker)
+        );
+    }
+    #[test]
+    fn eliminated_players_workers_leave_a_three_player_game() {
+        let mut game = Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            [
+                ((0, 0), (0, 1)),
+                ((2, 2), (2, 3)),
+                ((4, 4), (4, 3)),
+                ((17, 17), (17, 17)),
+            ],
+            Status::seats(3),
+        );
+        game.eliminate(0);
+        assert_eq!(game.player_statuses[0], Status::Dead);
+        assert_eq!(game.player_locations[0], ((17, 17), (17, 17)));
+        assert_eq!(game.to_move, 1);
+        assert_eq!(game.zobrist_hash(), zobrist::hash_game(&game));
+        // The last elimination ends the game, so the board is left as it was
+        game.eliminate(1);
+        assert_eq!(game.player_locations[1], ((2, 2), (2, 3)));
+    }
+    #[test]
+    fn main_loop_notifies_observers() {
+        #[derive(Default)]
+        struct Log {
+            placements: usize,
+            actions: usize,
+            eliminated: Vec<usize>,
+            winner: Option<usize>,
+        }
+        impl Observer for Log {
+            fn on_start_placed(&mut self, _: &Game, _: usize, _: StartLocation) {
+                self.placements += 1;
+            }
+            fn on_action(&mut self, _: &Game, _: usize, _: Action) {
+                self.actions += 1;
+            }
+            fn on_elimination(&mut self, _: &Game, elimination: &game_state::Elimination) {
+                assert_eq!(elimination.reason, EliminationReason::NoLegalAction);
+                self.eliminated.push(elimination.player_id);
+            }
+            fn on_game_over(&mut self, _: &Game, winner: usize) {
+                self.winner = Some(winner);
+            }
+        }
+
+        let player = random_choice_player::RandomChoice::new();
+        for _ in 0..20 {
+            let mut log = Log::default();
+            let record = main_loop(
+                [Some(&player), Some(&player), Some(&player), None],
+                [None; MAX_PLAYERS],
+                &mut [&mut log],
+            );
+            let winner = record.winner.unwrap();
+            assert_eq!(log.winner, Some(winner));
+            assert_eq!(log.placements, 3);
+            assert!(!log.eliminated.contains(&winner));
+            assert_eq!(record.actions.len(), log.actions);
+        }
+    }
+    #[test]
+    fn observers_are_told_apart_about_rejected_actions() {
+        struct OffTheBoard;
+        impl Player for OffTheBoard {
+            fn get_action(&self, _: &Game, _: usize) -> Action {
+                Action::new(Worker::One, (9, 9), (9, 8))
+            }
+            fn get_starting_position(
+                &self,
+                game: &Game,
+                placed: &[StartLocation],
+            ) -> StartLocation {
+                first_choice_player::FirstChoice::new().get_starting_position(game, placed)
+            }
+        }
+        #[derive(Default)]
+        struct Log {
+            actions: Vec<usize>,
+            rejected: Vec<(usize, IllegalAction)>,
+            eliminated: Vec<usize>,
+        }
+        impl Observer for Log {
+            fn on_action(&mut self, _: &Game, player_id: usize, _: Action) {
+                self.actions.push(player_id);
+            }
+            fn on_action_rejected(
+                &mut self,
+                _: &Game,
+                player_id: usize,
+                action: Action,
+                reason: IllegalAction,
+            ) {
+                assert_eq!(action, Action::new(Worker::One, (9, 9), (9, 8)));
+                self.rejected.push((player_id, reason));
+            }
+            fn on_elimination(&mut self, _: &Game, elimination: &game_state::Elimination) {
+                self.eliminated.push(elimination.player_id);
+            }
+        }
+
+        let player = random_choice_player::RandomChoice::new();
+        let mut log = Log::default();
+        let record = main_loop(
+            [Some(&player), Some(&OffTheBoard), None, None],
+            [None; MAX_PLAYERS],
+            &mut [&mut log],
+        );
+        assert_eq!(record.winner, Some(0));
+        assert_eq!(log.actions, vec![0]);
+        assert_eq!(log.rejected, vec![(1, IllegalAction::OutOfBounds)]);
+        assert_eq!(log.eliminated, vec![1]);
+        assert_eq!(record.actions.len(), log.actions.len());
+    }
+    #[test]
+    fn listed_god_actions_are_valid() {
+        for &god in God::ALL.iter() {
+            let mut game = game_with_gods(
+                [
+                    ((1, 1), (3, 3)),
+                    ((1, 2), (2, 2)),
+                    ((17, 17), (17, 17)),
+                    ((17, 17), (17, 17)),
+                ],
+                [Some(god), None, None, None],
+            );
+            game.set_height((0, 0), TowerStates::Level2);
+            game.set_height((2, 1), TowerStates::Level1);
+            game.set_height((3, 2), TowerStates::Capped);
+            let actions = game.list_possible_actions(0);
+            assert!(!actions.is_empty());
+            for action in actions {
+                assert_eq!(
+                    game.is_valid(0, action, false),
+                    Ok(()),
+                    "{:?} {:?}",
+                    god,
+                    action
+                );
+                let mut after = game;
+                assert!(after.apply_action(0, action, false).is_ok());
+            }
+        }
+    }
+    #[test]
+    fn apollo_swaps_with_opponent() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (0, 0)),
+                ((2, 3), (4, 4)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Apollo), None, None, None],
+        );
+        assert_eq!(
+            game.is_valid(0, Action::new(Worker::One, (2, 3), (2, 2)), false),
+            Err(IllegalAction::BuildOnOccupied)
+        );
+        let action = Action::new(Worker::One, (2, 3), (2, 4));
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(false)
+        );
+        assert_eq!(game.player_locations[0].0, (2, 3));
+        assert_eq!(game.player_locations[1].0, (2, 2));
+    }
+    #[test]
+    fn only_apollo_and_minotaur_move_into_opponents() {
+        for &god in God::ALL.iter() {
+            let game = game_with_gods(
+                [
+                    ((2, 2), (0, 0)),
+                    ((2, 3), (4, 4)),
+                    ((17, 17), (17, 17)),
+                    ((17, 17), (17, 17)),
+                ],
+                [Some(god), None, None, None],
+            );
+            assert_eq!(
+                game.can_move_to_square(0, Worker::One, (2, 3)),
+                god == God::Apollo || god == God::Minotaur
+            );
+        }
+    }
+    #[test]
+    fn artemis_moves_twice_but_not_back() {
+        let mut game = game_with_gods(
+            [
                 ((2, 2), (17, 17)),
                 ((17, 17), (17, 17)),
                 ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
             ],
-            player_statuses: [Status::Playing, Status::Dead, Status::Dead],
-        };
-        assert_eq!(game.list_possible_actions(0).len(), 64);
+            [Some(God::Artemis), None, None, None],
+        );
+        game.set_status(1, Status::Dead);
+        assert!(game.can_move_to_square(0, Worker::One, (0, 0)));
+        assert!(!game.can_move_to_square(0, Worker::One, (2, 2)));
+        game.set_height((0, 0), TowerStates::Level2);
+        assert!(!game.can_move_to_square(0, Worker::One, (0, 0)));
+        game.set_height((1, 1), TowerStates::Level1);
+        assert!(game.can_move_to_square(0, Worker::One, (0, 0)));
     }
     #[test]
-    fn is_near_player_returns_false_when_not() {
-        let game = Game {
-            board: [[TowerStates::Empty; 5]; 5],
-            player_locations: [((2, 2), (2, 3)), ((17, 17), (17, 17)), ((17, 17), (17, 17))],
-            player_statuses: [Status::Playing, Status::Dead, Status::Dead],
+    fn atlas_builds_domes_at_any_level() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (0, 0)),
+                ((4, 4), (4, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Atlas), None, None, None],
+        );
+        let action = Action {
+            dome: true,
+            ..Action::new(Worker::One, (2, 3), (2, 2))
         };
-        assert_eq!(game.is_near_player(0, (3, 4)), false);
+        assert_eq!(game.is_valid(0, action, false), Ok(()));
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(false)
+        );
+        assert_eq!(game.board[2][2], TowerStates::Capped);
+        game.set_player_gods([None; MAX_PLAYERS]);
+        assert_eq!(
+            game.is_valid(
+                0,
+                Action {
+                    movement: (1, 3),
+                    build: (1, 2),
+                    ..action
+                },
+                false
+            ),
+            Err(IllegalAction::DomeNotAllowed)
+        );
     }
     #[test]
-    fn is_near_player_returns_true_when_near_player() {
-        let game = Game {
-            board: [[TowerStates::Empty; 5]; 5],
-            player_locations: [((2, 2), (2, 3)), ((4, 2), (4, 3)), ((17, 17), (17, 17))],
-            player_statuses: [Status::Playing, Status::Playing, Status::Dead],
+    fn demeter_builds_twice_on_different_squares() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (0, 0)),
+                ((4, 4), (4, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Demeter), None, None, None],
+        );
+        let action = Action::new(Worker::One, (2, 3), (2, 2));
+        assert_eq!(
+            game.is_valid(
+                0,
+                Action {
+                    extra_build: Some((2, 2)),
+                    ..action
+                },
+                false
+            ),
+            Err(IllegalAction::ExtraBuildNotAllowed)
+        );
+        let action = Action {
+            extra_build: Some((1, 3)),
+            ..action
         };
-        assert_eq!(game.is_near_player(0, (3, 4)), true);
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(false)
+        );
+        assert_eq!(game.board[2][2], TowerStates::Level1);
+        assert_eq!(game.board[1][3], TowerStates::Level1);
     }
     #[test]
-    fn is_near_player_returns_false_when_only_near_player_is_dead() {
-        let game = Game {
-            board: [[TowerStates::Empty; 5]; 5],
-            player_locations: [((2, 2), (2, 3)), ((4, 2), (4, 3)), ((17, 17), (17, 17))],
-            player_statuses: [Status::Playing, Status::Dead, Status::Dead],
+    fn hephaestus_builds_twice_on_same_square_but_not_a_dome() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (0, 0)),
+                ((4, 4), (4, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Hephaestus), None, None, None],
+        );
+        let action = Action::new(Worker::One, (2, 3), (2, 2));
+        assert_eq!(
+            game.is_valid(
+                0,
+                Action {
+                    extra_build: Some((1, 3)),
+                    ..action
+                },
+                false
+            ),
+            Err(IllegalAction::ExtraBuildNotAllowed)
+        );
+        let action = Action {
+            extra_build: Some((2, 2)),
+            ..action
         };
-        assert_eq!(game.is_near_player(0, (3, 4)), false);
+        game.set_height((2, 2), TowerStates::Level2);
+        assert_eq!(
+            game.is_valid(0, action, false),
+            Err(IllegalAction::ExtraBuildNotAllowed)
+        );
+        game.set_height((2, 2), TowerStates::Level1);
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(false)
+        );
+        assert_eq!(game.board[2][2], TowerStates::Level3);
+    }
+    #[test]
+    fn minotaur_pushes_opponent_back() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (0, 0)),
+                ((2, 3), (3, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Minotaur), None, None, None],
+        );
+        game.set_height((4, 4), TowerStates::Capped);
+        assert!(!game.can_move_to_square(0, Worker::One, (3, 3)));
+        let action = Action::new(Worker::One, (2, 3), (2, 2));
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(false)
+        );
+        assert_eq!(game.player_locations[0].0, (2, 3));
+        assert_eq!(game.player_locations[1].0, (2, 4));
+        // Can't push off the board
+        assert!(!game.can_move_to_square(0, Worker::One, (2, 4)));
+    }
+    #[test]
+    fn athena_stops_opponents_moving_up_until_her_next_turn() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (4, 4)),
+                ((0, 0), (4, 0)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Athena), None, None, None],
+        );
+        game.set_height((2, 3), TowerStates::Level1);
+        game.set_height((0, 1), TowerStates::Level1);
+        let before = game;
+        let undo = game
+            .apply_action(0, Action::new(Worker::One, (2, 3), (2, 2)), false)
+            .unwrap();
+        assert_eq!(game.effects.moved_up, Some(0));
+        let loaded: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!(loaded, game);
+        assert_eq!(
+            game.check_move(1, Worker::One, (0, 1)),
+            Err(IllegalAction::MoveUpBlocked)
+        );
+        assert!(game
+            .list_possible_actions(1)
+            .iter()
+            .all(|action| action.movement != (0, 1)));
+        let mut undone = game;
+        undone.undo_action(undo);
+        assert_eq!(undone, before);
+
+        game.apply_action(1, Action::new(Worker::One, (1, 0), (2, 0)), false)
+            .unwrap();
+        game.apply_action(0, Action::new(Worker::Two, (4, 3), (4, 4)), false)
+            .unwrap();
+        assert_eq!(game.effects.moved_up, None);
+        assert!(game.can_move_to_square(1, Worker::One, (0, 1)));
+    }
+    #[test]
+    fn hera_stops_opponents_winning_on_the_edge() {
+        let mut game = game_with_gods(
+            [
+                ((1, 1), (4, 4)),
+                ((4, 0), (4, 2)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [None, Some(God::Hera), None, None],
+        );
+        game.set_height((1, 1), TowerStates::Level2);
+        game.set_height((0, 0), TowerStates::Level3);
+        game.set_height((2, 2), TowerStates::Level3);
+        assert!(!game.is_winning_move(0, Worker::One, (0, 0)));
+        assert!(game.is_winning_move(0, Worker::One, (2, 2)));
+        let action = Action::new(Worker::One, (0, 0), (0, 1));
+        assert!(game.list_possible_actions(0).contains(&action));
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(false)
+        );
+        // Hera's own moves onto the edge still win
+        assert!(!game.opposed_by_hera(1));
+    }
+    #[test]
+    fn pan_wins_by_dropping_two_levels() {
+        let mut game = game_with_gods(
+            [
+                ((2, 2), (0, 0)),
+                ((4, 4), (4, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            [Some(God::Pan), None, None, None],
+        );
+        game.set_height((2, 2), TowerStates::Level2);
+        let action = Action::new(Worker::One, (2, 3), (2, 2));
+        assert!(game.is_winning_move(0, Worker::One, (2, 3)));
+        game.set_player_gods([None; MAX_PLAYERS]);
+        assert!(!game.is_winning_move(0, Worker::One, (2, 3)));
+        game.set_player_gods([Some(God::Pan), None, None, None]);
+        assert_eq!(
+            game.apply_action(0, action, false).map(|undo| undo.won),
+            Ok(true)
+        );
+    }
+    #[test]
+    fn undo_action_restores_game() {
+        use rand::seq::SliceRandom;
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for &god in God::ALL.iter() {
+            let mut game = Game::new(
+                [[TowerStates::Empty; 5]; 5],
+                [
+                    ((1, 1), (3, 3)),
+                    ((1, 3), (3, 1)),
+                    ((2, 2), (0, 4)),
+                    ((17, 17), (17, 17)),
+                ],
+                Status::seats(3),
+            );
+            game.set_player_gods([Some(god), Some(God::Apollo), Some(God::Minotaur), None]);
+            for _ in 0..40 {
+                let player_id = game.to_move;
+                let actions = game.list_possible_actions(player_id);
+                for &action in actions.iter() {
+                    let before = game;
+                    let undo = game.apply_action(player_id, action, false).unwrap();
+                    game.undo_action(undo);
+                    assert_eq!(game, before, "{:?}", action);
+                }
+                match actions.choose(&mut rng) {
+                    Some(&action) => {
+                        if game.apply_action(player_id, action, true).unwrap().won {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+        }
     }
 }
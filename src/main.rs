@@ -12,23 +12,33 @@ impl RealPlayer {
     }
 }
 
+fn read_line() -> String {
+    let mut input = String::new();
+    if let Err(error) = std::io::stdin().read_line(&mut input) {
+        println!("Error: {}", error);
+    }
+    input
+}
+
+fn read_square(prompt: &str) -> Option<(u8, u8)> {
+    println!("{}", prompt);
+    match notation::parse_square(read_line().trim()) {
+        Ok(square) => Some(square),
+        Err(error) => {
+            println!("{}", error);
+            None
+        }
+    }
+}
+
 impl Player for RealPlayer {
     fn get_action(&self, game: &Game, player_id: usize) -> Action {
         println!("Player: {}", player_id);
-        let possible_actions = game.list_possible_actions(player_id);
-        if possible_actions.is_empty() {
-            println!("No possible moves left");
-            return (Worker::One, (0, 0), (0, 0));
-        }
+        let legal_moves = game.legal_moves(player_id);
         loop {
             let worker: Worker = {
                 println!("Enter which worker to select");
-                let mut input = String::new();
-                match std::io::stdin().read_line(&mut input) {
-                    Ok(_) => {}
-                    Err(error) => println!("Error: {}", error),
-                }
-                match &input.trim().to_lowercase() as &str {
+                match &read_line().trim().to_lowercase() as &str {
                     "o" => Worker::One,
                     "one" => Worker::One,
                     "1" => Worker::One,
@@ -41,80 +51,59 @@ impl Player for RealPlayer {
                     }
                 }
             };
-            if !possible_actions.iter().any(|(w, _, _)| *w == worker) {
+            let squares: Vec<String> = legal_moves
+                .iter()
+                .filter(|&&(legal_worker, _)| legal_worker == worker)
+                .map(|&(_, movement)| notation::square_name(movement))
+                .collect();
+            if squares.is_empty() {
                 println!("No possible moves with the chosen worker");
                 continue;
             }
-            let (move_x, move_y) = {
-                println!("Enter coordinates of where to move worker seperated by a space");
-                let mut input = String::new();
-                match std::io::stdin().read_line(&mut input) {
-                    Ok(_) => {}
-                    Err(error) => println!("Error: {}", error),
-                }
-                let coordinates: Vec<&str> = input.split_whitespace().collect();
-                let (mut x, mut y) = (0, 0);
-                let mut valid = false;
-                if coordinates.len() == 2 {
-                    if let Ok(x_tmp) = coordinates[1].parse::<u8>() {
-                        if let Ok(y_tmp) = coordinates[0].parse::<u8>() {
-                            x = x_tmp;
-                            y = y_tmp;
-                            valid = true;
-                        }
-                    };
+            println!("The worker can move to {}", squares.join(" "));
+            let movement = match read_square("Enter the square to move the worker to, e.g. B3") {
+                Some(square) => square,
+                None => continue,
+            };
+            let mut moved = *game;
+            match moved.apply_move(player_id, worker, movement) {
+                // The builds don't matter once the move has won
+                Ok(true) => {
+                    let action = game
+                        .possible_actions(player_id)
+                        .find(|action| (action.worker, action.movement) == (worker, movement))
+                        .unwrap();
+                    println!("{}", notation::action_to_string(game, player_id, action));
+                    return action;
                 }
-                if !valid {
-                    println!("Not valid coordinates");
+                Ok(false) => {}
+                Err(reason) => {
+                    println!("Worker cannot move to the chosen square: {}", reason);
                     continue;
-                } else {
-                    (x, y)
                 }
-            };
-            if !possible_actions
-                .iter()
-                .any(|(w, m, _)| (*w, *m) == (worker, (move_x, move_y)))
-            {
-                println!("Worker cannot move to the chosen square");
-                continue;
             }
-            let (build_x, build_y) = {
-                println!("Enter coordinates of where to build seperated by a space");
-                let mut input = String::new();
-                match std::io::stdin().read_line(&mut input) {
-                    Ok(_) => {}
-                    Err(error) => println!("Error: {}", error),
-                }
-                let coordinates: Vec<&str> = input.split_whitespace().collect();
-                let (mut x, mut y) = (0, 0);
-                let mut valid = false;
-                if coordinates.len() == 2 {
-                    if let Ok(x_tmp) = coordinates[1].parse::<u8>() {
-                        if let Ok(y_tmp) = coordinates[0].parse::<u8>() {
-                            x = x_tmp;
-                            y = y_tmp;
-                            valid = true;
-                        }
-                    };
-                }
-                if !valid {
-                    println!("Not valid coordinates");
+            let builds = moved.legal_builds(player_id, worker);
+            let options: Vec<String> = builds.iter().map(|build| build.to_string()).collect();
+            println!("The worker can build {}", options.join(" "));
+            let build: Build = loop {
+                println!("Enter the builds, e.g. C4, or C4* for a dome or C4^D4 for two");
+                let build = match read_line().parse::<Build>() {
+                    Ok(build) => build,
+                    Err(error) => {
+                        println!("{}", error);
+                        continue;
+                    }
+                };
+                let mut built = moved;
+                if let Err(reason) = built.apply_build(player_id, build) {
+                    println!("Worker cannot build at the chosen square: {}", reason);
                     continue;
-                } else {
-                    (x, y)
                 }
+                break build;
             };
-
-            if !possible_actions
-                .iter()
-                .any(|(w, m, b)| (*w, *m, *b) == (worker, (move_x, move_y), (build_x, build_y)))
-            {
-                println!(
-                    "Worker cannot move to the chosen square and then build at the chosen square"
-                );
-                continue;
-            }
-            return (worker, (move_x, move_y), (build_x, build_y));
+            let action = Action::with_build(worker, movement, build);
+            println!("{}", notation::action_to_string(game, player_id, action));
+            return action;
         }
     }
     fn get_starting_position(
@@ -124,76 +113,108 @@ impl Player for RealPlayer {
     ) -> StartLocation {
         game.print_board();
         loop {
-            let w1 = {
-                println!("Enter coordinates of first worker");
-                let mut input = String::new();
-                match std::io::stdin().read_line(&mut input) {
-                    Ok(_) => {}
-                    Err(error) => println!("Error: {}", error),
-                }
-                let coordinates: Vec<&str> = input.split_whitespace().collect();
-                let (mut x, mut y) = (0, 0);
-                let mut valid = false;
-                if coordinates.len() == 2 {
-                    if let Ok(x_tmp) = coordinates[1].parse::<u8>() {
-                        if let Ok(y_tmp) = coordinates[0].parse::<u8>() {
-                            x = x_tmp;
-                            y = y_tmp;
-                            valid = true;
-                        }
-                    };
-                }
-                if !valid
-                    || x > 4
-                    || y > 4
-                    || player_locations
-                        .iter()
-                        .any(|&(val1, val2)| val1 == (x, y) || val2 == (x, y))
-                {
-                    println!("Not valid coordinates");
-                    continue;
-                } else {
-                    (x, y)
-                }
-            };
-            let w2 = {
-                println!("Enter coordinates of second worker");
-                let mut input = String::new();
-                match std::io::stdin().read_line(&mut input) {
-                    Ok(_) => {}
-                    Err(error) => println!("Error: {}", error),
-                }
-                let coordinates: Vec<&str> = input.split_whitespace().collect();
-                let (mut x, mut y) = (0, 0);
-                let mut valid = false;
-                if coordinates.len() == 2 {
-                    if let Ok(x_tmp) = coordinates[1].parse::<u8>() {
-                        if let Ok(y_tmp) = coordinates[0].parse::<u8>() {
-                            x = x_tmp;
-                            y = y_tmp;
-                            valid = true;
-                        }
-                    };
-                }
-                if !valid
-                    || x > 4
-                    || y > 4
-                    || player_locations
-                        .iter()
-                        .any(|&(val1, val2)| val1 == (x, y) || val2 == (x, y))
-                {
-                    println!("Not valid coordinates");
+            println!("Enter the squares of both workers, e.g. B2,C3");
+            let (w1, w2) = match notation::parse_start_location(&read_line()) {
+                Ok(start_location) => start_location,
+                Err(error) => {
+                    println!("{}", error);
                     continue;
-                } else {
-                    (x, y)
                 }
             };
+            if !game
+                .list_possible_start_locations(player_locations)
+                .contains(&(w1, w2))
+            {
+                println!("The workers need two different free squares");
+                continue;
+            }
             return (w1, w2);
         }
     }
 }
 
+/// Steps through a saved replay, printing the board after each action
+fn view_replay(path: &str) {
+    let record = match std::fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| replay::parse_replay(&text).map_err(|error| error.to_string()))
+    {
+        Ok(record) => record,
+        Err(error) => {
+            println!("Failed to load {}: {}", path, error);
+            return;
+        }
+    };
+    let games: Vec<Game> = (0..=record.actions.len())
+        .map(|ply| record.game_at(ply).unwrap())
+        .collect();
+    let mut ply = 0;
+    loop {
+        games[ply].print_board();
+        if ply > 0 {
+            let (player_id, action) = record.actions[ply - 1];
+            println!(
+                "Move {}/{}: player {} played {}",
+                ply,
+                record.actions.len(),
+                player_id,
+                notation::action_to_string(&games[ply - 1], player_id, action)
+            );
+        }
+        for elimination in record.eliminations.iter().filter(|e| e.turn == ply) {
+            println!(
+                "Player {} is eliminated: {}",
+                elimination.player_id, elimination.reason
+            );
+        }
+        if ply == record.actions.len() {
+            match record.winner {
+                Some(winner) => println!("Player {} won the game", winner),
+                None => println!("The game was not finished"),
+            }
+        }
+        println!("Enter n for the next move, p for the previous one, a move number or q to quit");
+        match read_line().trim() {
+            "n" | "" => ply = (ply + 1).min(record.actions.len()),
+            "p" => ply = ply.saturating_sub(1),
+            "q" => return,
+            text => match text.parse::<usize>() {
+                Ok(number) if number <= record.actions.len() => ply = number,
+                _ => println!("Not a valid command"),
+            },
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let (Some("replay"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        view_replay(path);
+        return;
+    }
+    if let (Some("perft"), Some(depth)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let position = args[3..].join(" ");
+        match (depth.parse(), Game::from_position_string(&position)) {
+            (Ok(depth), Ok(game)) => {
+                let divided = perft::divide(&game, depth);
+                for &(action, count) in divided.iter() {
+                    println!(
+                        "{}: {}",
+                        notation::action_to_string(&game, game.to_move(), action),
+                        count
+                    );
+                }
+                println!(
+                    "Total: {}",
+                    divided.iter().map(|(_, count)| count).sum::<u64>()
+                );
+            }
+            (Err(_), _) => println!("Not a valid depth: {}", depth),
+            (_, Err(error)) => println!("Not a valid position: {}", error),
+        }
+        return;
+    }
+
     let mut line = String::new();
     let mut training_data: Vec<genetic_ai::TrainingData> = Vec::new();
     if let Ok(file) = std::fs::File::open("training_data.json") {
@@ -226,9 +247,10 @@ fn main() {
     }
 
     let mut new_ai = genetic_ai::GeneticAI::<nn::Tanh>::create_random(&mut rand::thread_rng());
-    new_ai.learn(&training_data, 1000);
+    new_ai.learn(&training_data, 1000, false);
 
-    let mut game_predictor = game_prediction::GamePrediction::<nn::Tanh>::create_random(&mut rand::thread_rng());
+    let mut game_predictor =
+        game_prediction::GamePrediction::<nn::Tanh>::create_random(&mut rand::thread_rng());
     game_predictor.learn(
         &training_data
             .iter()
@@ -237,6 +259,7 @@ fn main() {
             .collect::<Vec<_>>(),
         100000,
         0.0001,
+        false,
     );
     println!("{:?}", game_predictor);
     // new_ai.self_train(100, 5);
@@ -257,56 +280,44 @@ fn main() {
     let player1: &dyn Player = &new_ai;
     let player2: &dyn Player = &bruteforce::BruteForce::new(game_predictor, 3, -0.9);
 
-    let mut action_history: Option<_> = Some([vec![], vec![], vec![]]);
-    let mut start_location_history: Option<_> = Some([None, None, None]);
-
-    let mut scores = [0, 0];
-    for round in 0..1 {
-        let player1_first = rand::thread_rng().gen::<bool>();
-        let players: [Option<&dyn Player>; 3] = if player1_first {
-            [Some(player1), Some(player2), None]
-        } else {
-            [Some(player2), Some(player1), None]
-        };
-        let result = main_loop(players, false, &mut None, &mut None);
-        scores[if player1_first { result } else { 1 - result }] += 1;
-        println!("Scores: {:?}", scores);
-    }
+    println!(
+        "{}",
+        tournament::round_robin(&[("GeneticAI", player1), ("BruteForce", player2)], 1)
+    );
     let player1: &dyn Player = &RealPlayer::new();
-    let players: [Option<&dyn Player>; 3] = if rand::thread_rng().gen::<bool>() {
-        [Some(player1), Some(player2), None]
+    let players: [Option<&dyn Player>; MAX_PLAYERS] = if rand::thread_rng().gen::<bool>() {
+        [Some(player1), Some(player2), None, None]
     } else {
-        [Some(player2), Some(player1), None]
+        [Some(player2), Some(player1), None, None]
     };
-    let result = main_loop(
+    let record = main_loop(
         players,
-        true,
-        &mut action_history,
-        &mut start_location_history,
+        [None; MAX_PLAYERS],
+        &mut [&mut observer::BoardPrinter],
     );
-    println!("Player {} won the game", result);
-    for (player_id, action_list) in action_history.unwrap().iter().enumerate() {
-        for (game, action) in action_list.into_iter() {
-            training_data.push((player_id == result, player_id, *game, *action));
-        }
+    println!("Player {} won the game", record.winner.unwrap());
+    training_data.extend(record.training_data().unwrap());
+    start_location_training_data.extend(record.start_location_training_data().unwrap());
+    if let Ok(file) = std::fs::File::create("game_record.json") {
+        serde_json::to_writer(file, &record).unwrap();
+    } else {
+        println!("Failed to write game record");
     }
-
-    for (player_id, start_data) in start_location_history.unwrap().iter().enumerate() {
-        if let Some((game, start_locations)) = start_data {
-            start_location_training_data.push((
-                player_id == result,
-                *game,
-                start_locations.clone(),
-            ));
-        }
+    if std::fs::write(
+        "last_game.replay",
+        replay::to_replay_string(&record).unwrap(),
+    )
+    .is_err()
+    {
+        println!("Failed to write replay");
     }
     if let Ok(file) = std::fs::File::create("training_data.json") {
         let mut buf = std::io::LineWriter::new(file);
 
         for td in training_data.iter() {
-            buf.write(&serde_json::to_string(&td).unwrap().as_bytes())
+            buf.write_all(serde_json::to_string(&td).unwrap().as_bytes())
                 .unwrap();
-            buf.write(&[b'\n']).unwrap();
+            buf.write_all(b"\n").unwrap();
         }
     } else {
         println!("Failed to write training data");
@@ -316,9 +327,9 @@ fn main() {
         let mut buf = std::io::LineWriter::new(file);
 
         for sd in start_location_training_data.iter() {
-            buf.write(&serde_json::to_string(&sd).unwrap().as_bytes())
+            buf.write_all(serde_json::to_string(&sd).unwrap().as_bytes())
                 .unwrap();
-            buf.write(&[b'\n']).unwrap();
+            buf.write_all(b"\n").unwrap();
         }
     } else {
         println!("Failed to write start location training data");
@@ -16,7 +16,7 @@ impl ActivationFunction for Tanh {
     }
     fn inverse_activation(x: f32) -> f32 {
         let result = if x.abs() > 0.9999 {
-            (0.9999 as f32).atanh().copysign(x)
+            0.9999_f32.atanh().copysign(x)
         } else {
             x.atanh()
         };
@@ -44,6 +44,15 @@ impl<A: ActivationFunction> NeuralNet<A> {
         }
     }
 
+    /// A network with the given weights, the last one being the bias
+    pub fn from_weights(network: Vec<f32>) -> Self {
+        Self {
+            input_size: network.len() - 1,
+            network,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
     pub fn create_random(input_size: usize, rng: &mut rand::rngs::ThreadRng) -> Self {
         let mut network = Vec::with_capacity(input_size + 1);
         for _ in 0..(input_size + 1) {
@@ -119,7 +128,7 @@ impl<A: ActivationFunction> NeuralNet<A> {
                 })
                 .sum()
         }
-        let mut total_score_before: f64 = get_overall_score(self, &training_data);
+        let mut total_score_before: f64 = get_overall_score(self, training_data);
         let old_score = total_score_before;
         for i in 0..iterations {
             let mut overall_gradient = vec![0.0; self.network.len()];
@@ -135,7 +144,7 @@ impl<A: ActivationFunction> NeuralNet<A> {
             for (ptr, gradient) in new.network.iter_mut().zip(overall_gradient.iter()) {
                 *ptr += gradient * step_size;
             }
-            let new_score = get_overall_score(&new, &training_data);
+            let new_score = get_overall_score(&new, training_data);
             if new_score < total_score_before {
                 *self = new;
                 /*println!(
@@ -0,0 +1,772 @@
+//! Text forms of squares, actions, start placements and whole positions.
+//!
+//! Squares are named like a chess board as `print_board` draws it, with the letter giving the
+//! column and the number the row, so `(0, 0)` is `A1` and `(2, 1)` is `B3`. Actions are written
+//! as the worker, optionally the square it moves from, the square it moves to and its builds,
+//! e.g. `O:B2-C3^C4`. A second build is added as another `^D4`, and `*` after a build marks
+//! Atlas building a dome.
+
+use crate::*;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NotationError {
+    Square(String),
+    Worker(String),
+    Action(String),
+    Build(String),
+    StartLocation(String),
+}
+
+impl std::fmt::Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NotationError::Square(text) => {
+                write!(f, "invalid square {:?}, expected A1 to E5", text)
+            }
+            NotationError::Worker(text) => write!(f, "invalid worker {:?}, expected O or T", text),
+            NotationError::Action(text) => {
+                write!(f, "invalid action {:?}, expected e.g. O:B2-C3^C4", text)
+            }
+            NotationError::Build(text) => {
+                write!(
+                    f,
+                    "invalid builds {:?}, expected e.g. C4, C4* or C4^D4",
+                    text
+                )
+            }
+            NotationError::StartLocation(text) => {
+                write!(f, "invalid start location {:?}, expected e.g. B2,C3", text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+pub fn square_name((x, y): (u8, u8)) -> String {
+    if is_in_storage((x, y)) {
+        format!("{}{}", (b'A' + y) as char, x + 1)
+    } else {
+        "-".to_string()
+    }
+}
+
+pub fn parse_square(text: &str) -> Result<(u8, u8), NotationError> {
+    let bytes = text.as_bytes();
+    match bytes {
+        [column, row] => {
+            let y = column.to_ascii_uppercase().wrapping_sub(b'A');
+            let x = row.wrapping_sub(b'1');
+            if is_in_storage((x, y)) {
+                Ok((x, y))
+            } else {
+                Err(NotationError::Square(text.to_string()))
+            }
+        }
+        _ => Err(NotationError::Square(text.to_string())),
+    }
+}
+
+fn parse_worker(text: &str) -> Result<Worker, NotationError> {
+    match text {
+        "O" | "o" => Ok(Worker::One),
+        "T" | "t" => Ok(Worker::Two),
+        _ => Err(NotationError::Worker(text.to_string())),
+    }
+}
+
+impl std::fmt::Display for Build {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&square_name(self.build))?;
+        if self.dome {
+            f.write_str("*")?;
+        }
+        if let Some(extra_build) = self.extra_build {
+            write!(f, "^{}", square_name(extra_build))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}^{}",
+            self.worker,
+            square_name(self.movement),
+            self.builds()
+        )
+    }
+}
+
+impl std::str::FromStr for Build {
+    type Err = NotationError;
+
+    /// Reads the part of an action after the first `^`, e.g. `C4`, `C4*` or `C4^D4`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let mut builds = text.split('^');
+        let build = builds.next().unwrap();
+        let (build, dome) = match build.strip_suffix('*') {
+            Some(build) => (build, true),
+            None => (build, false),
+        };
+        let result = Build {
+            build: parse_square(build)?,
+            extra_build: builds.next().map(parse_square).transpose()?,
+            dome,
+        };
+        if builds.next().is_some() {
+            return Err(NotationError::Build(text.to_string()));
+        }
+        Ok(result)
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = NotationError;
+
+    /// Reads both `O:B2-C3^C4` and `O-C3^C4`, as the square moved from isn't part of an action
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || NotationError::Action(text.to_string());
+        let text = text.trim();
+        let dash = text.find('-').ok_or_else(error)?;
+        let worker = parse_worker(text[..dash].split(':').next().unwrap())?;
+        let (movement, builds) = text[dash + 1..].split_once('^').ok_or_else(error)?;
+        let movement = parse_square(movement)?;
+        let builds = builds.parse().map_err(|build_error| match build_error {
+            NotationError::Build(_) => error(),
+            build_error => build_error,
+        })?;
+        Ok(Action::with_build(worker, movement, builds))
+    }
+}
+
+/// Writes the action with the square the worker moves from, e.g. `O:B2-C3^C4`
+pub fn action_to_string(game: &Game, player_id: usize, action: Action) -> String {
+    let action_string = action.to_string();
+    format!(
+        "{}:{}{}",
+        action.worker,
+        square_name(game.worker_location(player_id, action.worker)),
+        &action_string[1..]
+    )
+}
+
+pub fn start_location_to_string((w1, w2): StartLocation) -> String {
+    format!("{},{}", square_name(w1), square_name(w2))
+}
+
+pub fn parse_start_location(text: &str) -> Result<StartLocation, NotationError> {
+    let mut squares = text.trim().split(',');
+    match (squares.next(), squares.next(), squares.next()) {
+        (Some(w1), Some(w2), None) => Ok((parse_square(w1.trim())?, parse_square(w2.trim())?)),
+        _ => Err(NotationError::StartLocation(text.to_string())),
+    }
+}
+
+/// Writes the whole game on one line: the tower levels row by row with 4 for a dome, each
+/// player's worker squares, their statuses, the player to move and their gods, e.g.
+/// `00000/01000/00200/00030/00004 A1,B2/C3,D4/-,- PPD 0 Apollo,-,-`. Smaller boards have fewer
+/// rows or fewer levels in each, e.g. `000/010/000` for 3x3. The last seat is only
+/// written out when it is in use. Team play adds `teams` at the end of the line, and Athena having
+/// moved up on her last turn adds `moved-up:` and her seat. Between `Game::apply_move` and
+/// `Game::apply_build`, `to-build:` and the worker that moved are added too.
+pub fn position_to_string(game: &Game) -> String {
+    let seats = if game.player_statuses[3] == Status::Dead
+        && game.player_locations[3] == ((17, 17), (17, 17))
+        && game.player_gods[3].is_none()
+        && game.to_move != 3
+    {
+        3
+    } else {
+        MAX_PLAYERS
+    };
+    let rows: Vec<String> = game
+        .board
+        .iter()
+        .take(game.size.height as usize)
+        .map(|row| {
+            row.iter()
+                .take(game.size.width as usize)
+                .map(|level| level.to_int().to_string())
+                .collect()
+        })
+        .collect();
+    let workers: Vec<String> = game.player_locations[..seats]
+        .iter()
+        .map(|&start_location| start_location_to_string(start_location))
+        .collect();
+    let statuses: String = game.player_statuses[..seats]
+        .iter()
+        .map(|&status| match status {
+            Status::Playing => 'P',
+            Status::Dead => 'D',
+        })
+        .collect();
+    let gods: Vec<String> = game.player_gods[..seats]
+        .iter()
+        .map(|god| god.map_or_else(|| "-".to_string(), |god| god.to_string()))
+        .collect();
+    let mut result = format!(
+        "{} {} {} {} {}",
+        rows.join("/"),
+        workers.join("/"),
+        statuses,
+        game.to_move,
+        gods.join(",")
+    );
+    if let Some(supply) = game.piece_supply {
+        let supply: Vec<String> = supply.iter().map(|left| left.to_string()).collect();
+        result.push(' ');
+        result.push_str(&supply.join(","));
+    }
+    if game.teams {
+        result.push_str(" teams");
+    }
+    if let Some(athena) = game.effects.moved_up {
+        result.push_str(&format!(" moved-up:{}", athena));
+    }
+    if let Some(worker) = game.effects.to_build {
+        result.push_str(&format!(" to-build:{}", worker));
+    }
+    result
+}
+
+/// Where a position string went wrong. Rows, columns and players count from 0.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PositionError {
+    /// Expected 4 to 6 space separated fields, not counting `teams`, `moved-up` and `to-build`
+    FieldCount(usize),
+    RowCount(usize),
+    RowLength {
+        row: usize,
+        length: usize,
+    },
+    Level {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+    PlayerCount(usize),
+    WorkerCount {
+        player: usize,
+        count: usize,
+    },
+    WorkerSquare {
+        player: usize,
+        found: String,
+    },
+    /// Two workers were put on the same square
+    SharedSquare(String),
+    /// The statuses didn't cover the same seats as the workers
+    StatusCount(usize),
+    Status {
+        player: usize,
+        found: char,
+    },
+    ToMove(String),
+    GodCount(usize),
+    God {
+        player: usize,
+        found: String,
+    },
+    PieceSupply(String),
+    MovedUp(String),
+    ToBuild(String),
+}
+
+impl std::fmt::Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PositionError::FieldCount(count) => write!(
+                f,
+                "expected 4 to 6 fields separated by spaces (board, workers, statuses, player \
+                 to move and optionally gods and pieces left), found {}",
+                count
+            ),
+            PositionError::RowCount(count) => write!(
+                f,
+                "expected 3 to 5 board rows separated by '/', found {}",
+                count
+            ),
+            PositionError::RowLength { row, length } => write!(
+                f,
+                "expected 3 to 5 levels in board row {}, as many as in the first row, found {}",
+                row + 1,
+                length
+            ),
+            PositionError::Level { row, column, found } => write!(
+                f,
+                "expected a level from 0 to 4 at {}, found {:?}",
+                square_name((*row as u8, *column as u8)),
+                found
+            ),
+            PositionError::PlayerCount(count) => write!(
+                f,
+                "expected workers for 3 or 4 players separated by '/', found {}",
+                count
+            ),
+            PositionError::WorkerCount { player, count } => write!(
+                f,
+                "expected 2 worker squares for player {}, found {}",
+                player, count
+            ),
+            PositionError::WorkerSquare { player, found } => write!(
+                f,
+                "expected a square from A1 to E5 or '-' for player {}'s worker, found {:?}",
+                player, found
+            ),
+            PositionError::SharedSquare(square) => {
+                write!(f, "more than one worker is on {}", square)
+            }
+            PositionError::StatusCount(count) => {
+                write!(f, "expected a status for each player, found {}", count)
+            }
+            PositionError::Status { player, found } => write!(
+                f,
+                "expected P or D for player {}'s status, found {:?}",
+                player, found
+            ),
+            PositionError::ToMove(found) => {
+                write!(
+                    f,
+                    "expected the seat of the player to move, found {:?}",
+                    found
+                )
+            }
+            PositionError::GodCount(count) => write!(
+                f,
+                "expected a god for each player separated by ',', found {}",
+                count
+            ),
+            PositionError::God { player, found } => write!(
+                f,
+                "expected a god or '-' for player {}, found {:?}",
+                player, found
+            ),
+            PositionError::MovedUp(found) => {
+                write!(f, "expected a seat after 'moved-up:', found {:?}", found)
+            }
+            PositionError::ToBuild(found) => {
+                write!(f, "expected O or T after 'to-build:', found {:?}", found)
+            }
+            PositionError::PieceSupply(found) => write!(
+                f,
+                "expected the number of level 1, 2 and 3 pieces and domes left separated by \
+                 ',', found {:?}",
+                found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Reads a position written by `position_to_string`. The gods can be left off, and so can the
+/// pieces left when the supply isn't limited.
+pub fn parse_position(text: &str) -> Result<Game, PositionError> {
+    let mut fields: Vec<&str> = text.split_whitespace().collect();
+    let mut teams = false;
+    let mut effects = TurnEffects::default();
+    while let Some(&flag) = fields.last() {
+        if flag == "teams" {
+            teams = true;
+        } else if let Some(seat) = flag.strip_prefix("moved-up:") {
+            match seat.parse::<usize>() {
+                Ok(seat) if seat < MAX_PLAYERS => effects.moved_up = Some(seat),
+                _ => return Err(PositionError::MovedUp(seat.to_string())),
+            }
+        } else if let Some(worker) = flag.strip_prefix("to-build:") {
+            effects.to_build =
+                Some(parse_worker(worker).map_err(|_| PositionError::ToBuild(worker.to_string()))?);
+        } else {
+            break;
+        }
+        fields.pop();
+    }
+    if fields.len() < 4 || fields.len() > 6 {
+        return Err(PositionError::FieldCount(fields.len()));
+    }
+
+    let mut board = [[TowerStates::Empty; 5]; 5];
+    let rows: Vec<&str> = fields[0].split('/').collect();
+    let width = rows[0].chars().count();
+    // Checked before narrowing to u8, which could wrap a long row or a lot of rows into range
+    if !(3..=5).contains(&rows.len()) {
+        return Err(PositionError::RowCount(rows.len()));
+    }
+    if !(3..=5).contains(&width) {
+        return Err(PositionError::RowLength {
+            row: 0,
+            length: width,
+        });
+    }
+    let size = BoardSize::new(width as u8, rows.len() as u8).unwrap();
+    for (x, row) in rows.iter().enumerate() {
+        let length = row.chars().count();
+        if length != width {
+            return Err(PositionError::RowLength { row: x, length });
+        }
+        for (y, level) in row.chars().enumerate() {
+            board[x][y] = match level {
+                '0' => TowerStates::Empty,
+                '1' => TowerStates::Level1,
+                '2' => TowerStates::Level2,
+                '3' => TowerStates::Level3,
+                '4' => TowerStates::Capped,
+                found => {
+                    return Err(PositionError::Level {
+                        row: x,
+                        column: y,
+                        found,
+                    })
+                }
+            };
+        }
+    }
+
+    let mut player_locations = [((17, 17), (17, 17)); MAX_PLAYERS];
+    let players: Vec<&str> = fields[1].split('/').collect();
+    let seats = players.len();
+    if !(3..=MAX_PLAYERS).contains(&seats) {
+        return Err(PositionError::PlayerCount(seats));
+    }
+    if let Some(athena) = effects.moved_up.filter(|&athena| athena >= seats) {
+        return Err(PositionError::MovedUp(athena.to_string()));
+    }
+    let mut occupied = Vec::new();
+    for (player, (location, workers)) in player_locations.iter_mut().zip(players).enumerate() {
+        let squares = workers
+            .split(',')
+            .map(|square| match square {
+                "-" => Ok((17, 17)),
+                _ => parse_square(square)
+                    .ok()
+                    .filter(|&square| size.contains(square))
+                    .ok_or_else(|| PositionError::WorkerSquare {
+                        player,
+                        found: square.to_string(),
+                    }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if squares.len() != 2 {
+            return Err(PositionError::WorkerCount {
+                player,
+                count: squares.len(),
+            });
+        }
+        for &square in squares.iter().filter(|&&square| size.contains(square)) {
+            if occupied.contains(&square) {
+                return Err(PositionError::SharedSquare(square_name(square)));
+            }
+            occupied.push(square);
+        }
+        *location = (squares[0], squares[1]);
+    }
+
+    let mut player_statuses = [Status::Dead; MAX_PLAYERS];
+    let statuses: Vec<char> = fields[2].chars().collect();
+    if statuses.len() != seats {
+        return Err(PositionError::StatusCount(statuses.len()));
+    }
+    for (player, (status, &found)) in player_statuses.iter_mut().zip(&statuses).enumerate() {
+        *status = match found {
+            'P' => Status::Playing,
+            'D' => Status::Dead,
+            found => return Err(PositionError::Status { player, found }),
+        };
+    }
+
+    let to_move = match fields[3].parse::<usize>() {
+        Ok(to_move) if to_move < seats => to_move,
+        _ => return Err(PositionError::ToMove(fields[3].to_string())),
+    };
+
+    let mut player_gods = [None; MAX_PLAYERS];
+    if let Some(gods) = fields.get(4) {
+        let gods: Vec<&str> = gods.split(',').collect();
+        if gods.len() != seats {
+            return Err(PositionError::GodCount(gods.len()));
+        }
+        for (player, (player_god, &name)) in player_gods.iter_mut().zip(&gods).enumerate() {
+            if name != "-" {
+                *player_god = Some(
+                    *God::ALL
+                        .iter()
+                        .find(|god| god.to_string() == name)
+                        .ok_or_else(|| PositionError::God {
+                            player,
+                            found: name.to_string(),
+                        })?,
+                );
+            }
+        }
+    }
+
+    let piece_supply = match fields.get(5) {
+        Some(supply) => {
+            let error = || PositionError::PieceSupply(supply.to_string());
+            let left = supply
+                .split(',')
+                .map(|left| left.parse::<u8>().map_err(|_| error()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if left.len() != 4 || left.iter().zip(PIECE_SUPPLY.iter()).any(|(l, all)| l > all) {
+                return Err(error());
+            }
+            Some([left[0], left[1], left[2], left[3]])
+        }
+        None => None,
+    };
+
+    let mut game = Game::new(board, player_locations, player_statuses);
+    game.player_gods = player_gods;
+    game.to_move = to_move;
+    game.piece_supply = piece_supply;
+    game.teams = teams;
+    game.effects = effects;
+    game.size = size;
+    game.refresh_hash();
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_follow_the_printed_board() {
+        assert_eq!(square_name((0, 0)), "A1");
+        assert_eq!(square_name((2, 1)), "B3");
+        assert_eq!(square_name((4, 4)), "E5");
+        for x in 0..5 {
+            for y in 0..5 {
+                assert_eq!(parse_square(&square_name((x, y))), Ok((x, y)));
+            }
+        }
+        assert_eq!(parse_square("b3"), Ok((2, 1)));
+        assert!(parse_square("F1").is_err());
+        assert!(parse_square("A0").is_err());
+        assert!(parse_square("A10").is_err());
+    }
+
+    #[test]
+    fn actions_round_trip() {
+        let game = Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            [
+                ((1, 1), (3, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            Status::seats(3),
+        );
+        let action = Action::new(Worker::One, (2, 2), (3, 2));
+        assert_eq!(action.to_string(), "O-C3^C4");
+        assert_eq!(action_to_string(&game, 0, action), "O:B2-C3^C4");
+        assert_eq!("O:B2-C3^C4".parse(), Ok(action));
+        assert_eq!("O-C3^C4".parse(), Ok(action));
+        let action = Action {
+            worker: Worker::Two,
+            extra_build: Some((3, 3)),
+            dome: true,
+            ..action
+        };
+        assert_eq!(action.to_string(), "T-C3^C4*^D4");
+        assert_eq!("T-C3^C4*^D4".parse(), Ok(action));
+        assert_eq!(action.builds().to_string(), "C4*^D4");
+        assert_eq!("C4*^D4".parse(), Ok(action.builds()));
+        assert!("X-C3^C4".parse::<Action>().is_err());
+        assert!("O-C3".parse::<Action>().is_err());
+        assert!("O-C3^C4^D4^E4".parse::<Action>().is_err());
+        assert_eq!(
+            "C4^D4^E4".parse::<Build>(),
+            Err(NotationError::Build("C4^D4^E4".to_string()))
+        );
+    }
+
+    #[test]
+    fn start_locations_round_trip() {
+        let start_location = ((1, 1), (2, 2));
+        assert_eq!(start_location_to_string(start_location), "B2,C3");
+        assert_eq!(parse_start_location("B2, C3"), Ok(start_location));
+        assert!(parse_start_location("B2").is_err());
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let mut game = Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            [
+                ((0, 0), (1, 1)),
+                ((2, 2), (3, 3)),
+                ((17, 17), (17, 17)),
+                ((17, 17), (17, 17)),
+            ],
+            Status::seats(2),
+        );
+        game.board[1][1] = TowerStates::Level1;
+        game.board[4][4] = TowerStates::Capped;
+        game.player_gods[0] = Some(God::Apollo);
+        game.to_move = 1;
+        game.refresh_hash();
+        let position = game.to_position_string();
+        assert_eq!(
+            position,
+            "00000/01000/00000/00000/00004 A1,B2/C3,D4/-,- PPD 1 Apollo,-,-"
+        );
+        assert_eq!(Game::from_position_string(&position), Ok(game));
+
+        game.limit_pieces();
+        let position = game.to_position_string();
+        assert!(position.ends_with(" Apollo,-,- 20,17,13,17"));
+        assert_eq!(Game::from_position_string(&position), Ok(game));
+
+        let position = "00000/00000/00000/00000/00000 A1,B2/C3,D4/E5,A5/-,- PPPD 3 -,-,-,Pan teams";
+        let game = Game::from_position_string(position).unwrap();
+        assert!(game.teams);
+        assert_eq!(game.to_position_string(), position);
+
+        let position = "000/010/002/000 A1,C3/B2,-/-,- PPD 0 -,-,-";
+        let game = Game::from_position_string(position).unwrap();
+        assert_eq!(game.size, BoardSize::new(3, 4).unwrap());
+        assert_eq!(game.height((2, 2)), TowerStates::Level2);
+        assert_eq!(game.to_position_string(), position);
+
+        let position = "00000/00000/00000/00000/00000 A1,B2/C3,D4/-,- PPD 1 Athena,-,- moved-up:0";
+        let game = Game::from_position_string(position).unwrap();
+        assert_eq!(game.effects.moved_up, Some(0));
+        assert_eq!(game.to_position_string(), position);
+
+        let mut game = Game::from_position_string(position).unwrap();
+        game.apply_move(1, Worker::Two, (2, 3)).unwrap();
+        let position = game.to_position_string();
+        assert!(position.ends_with(" moved-up:0 to-build:T"));
+        assert_eq!(Game::from_position_string(&position), Ok(game));
+    }
+
+    #[test]
+    fn position_errors_say_where() {
+        let cases = [
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD",
+                PositionError::FieldCount(3),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 0 -,-,- 22,18,15,18",
+                PositionError::PieceSupply("22,18,15,18".to_string()),
+            ),
+            (
+                "00000/00000 A1,B2/-,-/-,- PDD 0",
+                PositionError::RowCount(2),
+            ),
+            (
+                "00000/0000/00000/00000/00000 A1,B2/-,-/-,- PDD 0",
+                PositionError::RowLength { row: 1, length: 4 },
+            ),
+            (
+                "000/000/000 A1,D1/-,-/-,- PDD 0",
+                PositionError::WorkerSquare {
+                    player: 0,
+                    found: "D1".to_string(),
+                },
+            ),
+            (
+                "00000/00000/00000/00000/00050 A1,B2/-,-/-,- PDD 0",
+                PositionError::Level {
+                    row: 4,
+                    column: 3,
+                    found: '5',
+                },
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,- PDD 0",
+                PositionError::PlayerCount(2),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-/-,- PDD 0",
+                PositionError::WorkerCount {
+                    player: 1,
+                    count: 1,
+                },
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/F1,- PDD 0",
+                PositionError::WorkerSquare {
+                    player: 2,
+                    found: "F1".to_string(),
+                },
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/B2,C3/-,- PPD 0",
+                PositionError::SharedSquare("B2".to_string()),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PD 0",
+                PositionError::StatusCount(2),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDX 0",
+                PositionError::Status {
+                    player: 2,
+                    found: 'X',
+                },
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 3",
+                PositionError::ToMove("3".to_string()),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 0 Pan",
+                PositionError::GodCount(1),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 0 Pan,-,Zeus",
+                PositionError::God {
+                    player: 2,
+                    found: "Zeus".to_string(),
+                },
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 0 moved-up:4",
+                PositionError::MovedUp("4".to_string()),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 0 moved-up:3",
+                PositionError::MovedUp("3".to_string()),
+            ),
+            (
+                "00000/00000/00000/00000/00000 A1,B2/-,-/-,- PDD 0 to-build:B",
+                PositionError::ToBuild("B".to_string()),
+            ),
+        ];
+        for (position, error) in cases.iter() {
+            assert_eq!(
+                parse_position(position).as_ref(),
+                Err(error),
+                "{}",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_boards_are_errors() {
+        // 259 wraps round to 3 as a u8
+        let long_row = format!("{}/000/000 A1,B2/-,-/-,- PDD 0", "0".repeat(259));
+        assert_eq!(
+            parse_position(&long_row),
+            Err(PositionError::RowLength {
+                row: 0,
+                length: 259
+            })
+        );
+        let many_rows = format!("{} A1,B2/-,-/-,- PDD 0", vec!["000"; 259].join("/"));
+        assert_eq!(
+            parse_position(&many_rows),
+            Err(PositionError::RowCount(259))
+        );
+    }
+}
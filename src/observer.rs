@@ -0,0 +1,45 @@
+use crate::game_state::Elimination;
+use crate::*;
+
+/// Follows a game played by `main_loop`. Every callback does nothing unless overridden.
+pub trait Observer {
+    /// Called before the player is asked for an action
+    fn on_turn_start(&mut self, _game: &Game, _player_id: usize) {}
+
+    /// `game` is the position before the workers were placed
+    fn on_start_placed(&mut self, _game: &Game, _player_id: usize, _start_location: StartLocation) {
+    }
+
+    /// `game` is the position the action was chosen in
+    fn on_action(&mut self, _game: &Game, _player_id: usize, _action: Action) {}
+
+    /// An illegal action, which isn't played. The player is eliminated for it straight after.
+    fn on_action_rejected(
+        &mut self,
+        _game: &Game,
+        _player_id: usize,
+        _action: Action,
+        _reason: IllegalAction,
+    ) {
+    }
+
+    fn on_elimination(&mut self, _game: &Game, _elimination: &Elimination) {}
+
+    fn on_game_over(&mut self, _game: &Game, _winner: usize) {}
+}
+
+/// Prints the board before every turn, for following a game in the terminal
+pub struct BoardPrinter;
+
+impl Observer for BoardPrinter {
+    fn on_turn_start(&mut self, game: &Game, _: usize) {
+        game.print_board();
+    }
+
+    fn on_elimination(&mut self, _: &Game, elimination: &Elimination) {
+        println!(
+            "Player {} is eliminated: {}",
+            elimination.player_id, elimination.reason
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Counting every line of play to a fixed depth, for checking the action generator against
+//! known totals.
+
+use crate::*;
+
+/// Number of positions reached after `depth` turns, one action per turn. A position where the
+/// game is over counts once, however many turns are left. A stuck player is eliminated without
+/// using up a turn when the game goes on without them.
+pub fn perft(game: &Game, depth: usize) -> u64 {
+    count(&mut game.to_owned(), depth)
+}
+
+/// `perft` split up by the first action, in the order `list_possible_actions` gives them
+pub fn divide(game: &Game, depth: usize) -> Vec<(Action, u64)> {
+    let mut game = *game;
+    if depth == 0 {
+        return Vec::new();
+    }
+    let player_id = game.to_move;
+    game.possible_actions(player_id)
+        .map(|action| (action, count_after(&mut game, player_id, action, depth)))
+        .collect()
+}
+
+fn count(game: &mut Game, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let player_id = game.to_move;
+    let mut actions = game.possible_actions(player_id).peekable();
+    if actions.peek().is_none() {
+        let mut next = *game;
+        next.eliminate(player_id);
+        if next.one_side_left() {
+            return 1;
+        }
+        return count(&mut next, depth);
+    }
+    actions
+        .map(|action| count_after(game, player_id, action, depth))
+        .sum()
+}
+
+fn count_after(game: &mut Game, player_id: usize, action: Action, depth: usize) -> u64 {
+    let undo = game.apply_action(player_id, action, true).unwrap();
+    let result = if undo.won { 1 } else { count(game, depth - 1) };
+    game.undo_action(undo);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts for depths 1 to 3. The positions without gods were counted again with the original
+    // tuple based list_possible_actions and apply_action, from before gods and bitboards, taking
+    // turns and eliminating stuck players the same way as perft. The positions with gods have
+    // only been counted by this crate past depth 1, which `known_counts_match_is_valid` checks.
+    const KNOWN_COUNTS: [(&str, [u64; 3]); 7] = [
+        // One worker in the middle has 8 moves with 8 builds each
+        (
+            "00000/00000/00000/00000/00000 C3,-/-,-/-,- PDD 0",
+            [64, 3264, 148504],
+        ),
+        (
+            "00000/00000/00000/00000/00000 B2,D4/B4,D2/-,- PPD 0",
+            [80, 6176, 426384],
+        ),
+        // The original generator counted a worker already on level 3 moving onto another level 3
+        // as a win, which the rules don't, so it was changed to only count moving up for this one
+        (
+            "01200/02310/00120/10030/00004 A1,C2/C3,D5/-,- PPD 1",
+            [58, 3031, 150930],
+        ),
+        (
+            "00000/00000/00000/00000/00000 B2,D4/B4,D2/C3,E5 PPP 0",
+            [61, 3858, 166868],
+        ),
+        // Only counted by this crate past depth 1
+        (
+            "01200/00300/00040/10000/00002 A1,B2/C3,D5/E2,E1 PPP 1 Apollo,Minotaur,Demeter",
+            [53, 5275, 164647],
+        ),
+        // Only counted by this crate past depth 1
+        (
+            "00100/01210/02320/01210/00100 B2,D4/B4,D2/C1,C5 PPP 2 Artemis,Atlas,Hephaestus",
+            [46, 4648, 367978],
+        ),
+        // Player 0 is stuck, so player 1 moves first
+        (
+            "04000/44000/00000/00000/00000 A1,-/C3,D4/E5,C5 PPP 0",
+            [65, 1945, 121947],
+        ),
+    ];
+
+    #[test]
+    fn perft_matches_known_counts() {
+        for &(position, counts) in KNOWN_COUNTS.iter() {
+            let game = Game::from_position_string(position).unwrap();
+            for (depth, &expected) in counts.iter().enumerate() {
+                assert_eq!(
+                    perft(&game, depth + 1),
+                    expected,
+                    "{} {}",
+                    position,
+                    depth + 1
+                );
+            }
+        }
+    }
+
+    /// Depth 1 worked out by trying every action near the worker with `is_valid`, without the
+    /// action generator
+    fn count_by_is_valid(game: &Game) -> u64 {
+        let player_id = game.to_move;
+        let mut total = 0;
+        for &worker in [Worker::One, Worker::Two].iter() {
+            for movement in game.size.squares() {
+                let builds = neighbours(movement);
+                let builds = builds.iter().filter(|&&build| game.size.contains(build));
+                for &build in builds.clone() {
+                    let extra_builds =
+                        std::iter::once(None).chain(builds.clone().map(|&b| Some(b)));
+                    for extra_build in extra_builds {
+                        for &dome in [false, true].iter() {
+                            let action = Action {
+                                worker,
+                                movement,
+                                build,
+                                extra_build,
+                                dome,
+                            };
+                            // Winning moves are only listed once, without any build options
+                            let listed = !game.is_winning_move(player_id, worker, movement)
+                                || (extra_build.is_none() && !dome);
+                            if listed && game.is_valid(player_id, action, false).is_ok() {
+                                total += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if total == 0 {
+            let mut next = *game;
+            next.eliminate(player_id);
+            return count_by_is_valid(&next);
+        }
+        total
+    }
+
+    #[test]
+    fn known_counts_match_is_valid() {
+        for &(position, counts) in KNOWN_COUNTS.iter() {
+            let game = Game::from_position_string(position).unwrap();
+            assert_eq!(count_by_is_valid(&game), counts[0], "{}", position);
+        }
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let (position, counts) = KNOWN_COUNTS[4];
+        let game = Game::from_position_string(position).unwrap();
+        let divided = divide(&game, 2);
+        assert_eq!(divided.len() as u64, counts[0]);
+        assert_eq!(
+            divided.iter().map(|(_, count)| count).sum::<u64>(),
+            counts[1]
+        );
+        for &symmetry in Symmetry::ALL.iter() {
+            assert_eq!(perft(&game.transform(symmetry).unwrap(), 2), counts[1]);
+        }
+    }
+}
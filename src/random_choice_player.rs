@@ -1,6 +1,28 @@
 use crate::*;
 use rand::prelude::*;
 
+/// The squares the simple players pick their starting squares from
+const START_SQUARES: [(u8, u8); 6] = [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (1, 0)];
+
+/// Two different squares out of the free `START_SQUARES`, chosen at random. Once fewer than two of
+/// those are left, the first other free squares make up the difference.
+pub(crate) fn random_start_location(game: &Game, placed: &[StartLocation]) -> StartLocation {
+    let mut values: Vec<(u8, u8)> = game
+        .free_squares(placed)
+        .filter(|square| START_SQUARES.contains(square))
+        .collect();
+    if values.len() < 2 {
+        let missing = 2 - values.len();
+        values.extend(
+            game.free_squares(placed)
+                .filter(|square| !START_SQUARES.contains(square))
+                .take(missing),
+        );
+    }
+    with_rng(|rng| values.shuffle(rng));
+    (values[0], values[1])
+}
+
 pub struct RandomChoice {}
 
 impl RandomChoice {
@@ -8,28 +30,22 @@ impl RandomChoice {
         Self {}
     }
 }
+
+impl Default for RandomChoice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl Player for RandomChoice {
     fn get_action(&self, game: &Game, player_id: usize) -> Action {
-        let mut possible_actions = game.list_possible_actions(player_id);
-        if !possible_actions.is_empty() {
-            possible_actions.shuffle(&mut rand::thread_rng());
-            possible_actions[0]
-        } else {
-            (Worker::One, (0, 0), (0, 0))
-        }
+        with_rng(|rng| game.possible_actions(player_id).choose(rng)).unwrap()
     }
 
-    fn get_starting_position(&self, _: &Game, player_locations: &[StartLocation]) -> StartLocation {
-        let mut values: Vec<(u8, u8)> = Vec::new();
-        for &i in [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (1, 0)].iter() {
-            if player_locations
-                .iter()
-                .all(|&(val1, val2)| val1 != i && val2 != i)
-            {
-                values.push(i);
-            }
-        }
-        values.shuffle(&mut rand::thread_rng());
-        (values[0], values[1])
+    fn get_starting_position(
+        &self,
+        game: &Game,
+        player_locations: &[StartLocation],
+    ) -> StartLocation {
+        random_start_location(game, player_locations)
     }
 }
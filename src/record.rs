@@ -0,0 +1,272 @@
+use crate::game_state::Elimination;
+use crate::*;
+
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerInfo {
+    pub name: String,
+    pub kind: String,
+}
+
+impl PlayerInfo {
+    pub fn new(player: &dyn Player) -> Self {
+        Self {
+            name: player.name(),
+            kind: player.kind().to_string(),
+        }
+    }
+}
+
+/// Everything needed to replay a game and learn from it
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GameRecord {
+    /// `None` for seats nobody played in
+    pub players: [Option<PlayerInfo>; MAX_PLAYERS],
+    pub player_gods: [Option<God>; MAX_PLAYERS],
+    /// Whether the game was played with a limited supply of building pieces
+    #[serde(default)]
+    pub limited_pieces: bool,
+    /// Whether seats 0 and 2 played as a team against seats 1 and 3
+    #[serde(default)]
+    pub teams: bool,
+    #[serde(default)]
+    pub board_size: BoardSize,
+    /// Seed the players drew their random choices from, see `play_game`
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// When the game was played, in seconds since the Unix epoch
+    pub date: u64,
+    /// Worker placements in the order they were made, which is seat order
+    pub start_locations: Vec<StartLocation>,
+    pub actions: Vec<(usize, Action)>,
+    pub eliminations: Vec<Elimination>,
+    pub winner: Option<usize>,
+}
+
+impl GameRecord {
+    /// Records the game so far. `players` has to be `Some` for exactly the seats in the game.
+    pub fn new(state: &GameState, players: [Option<PlayerInfo>; MAX_PLAYERS]) -> Self {
+        Self {
+            players,
+            player_gods: state.game().player_gods,
+            limited_pieces: state.game().piece_supply.is_some(),
+            teams: state.game().teams,
+            board_size: state.game().size,
+            seed: state.seed(),
+            date: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            start_locations: state.start_locations().to_vec(),
+            actions: state.actions().to_vec(),
+            eliminations: state.eliminations().to_vec(),
+            winner: state.result(),
+        }
+    }
+
+    /// The game as it was set up, before any workers were placed. Given to `play_game` with the
+    /// same players, it plays out the same way again.
+    pub fn initial_state(&self) -> GameState {
+        let mut player_statuses = [Status::Dead; MAX_PLAYERS];
+        for (status, player) in player_statuses.iter_mut().zip(self.players.iter()) {
+            if player.is_some() {
+                *status = Status::Playing;
+            }
+        }
+        let mut state = GameState::new(player_statuses, self.player_gods);
+        if self.limited_pieces {
+            state.limit_pieces();
+        }
+        if self.teams {
+            state.play_in_teams();
+        }
+        state.set_board_size(self.board_size);
+        if let Some(seed) = self.seed {
+            state.set_seed(seed);
+        }
+        state
+    }
+
+    /// Whether the player won, on their own or along with their teammate
+    pub fn has_won(&self, player_id: usize) -> bool {
+        match self.winner {
+            Some(winner) => on_same_team(self.teams, winner, player_id),
+            None => false,
+        }
+    }
+
+    /// Places the workers, or eliminates the players who forfeited instead. `on_placement` is
+    /// given the state before each placement.
+    fn replay_placements(
+        &self,
+        mut on_placement: impl FnMut(&GameState, StartLocation),
+    ) -> Result<GameState, IllegalAction> {
+        let mut state = self.initial_state();
+        let mut start_locations = self.start_locations.iter();
+        while state.phase() == Phase::Placement {
+            let player_id = state.current_player();
+            let forfeit = self.eliminations.iter().find(|elimination| {
+                elimination.player_id == player_id
+                    && matches!(elimination.reason, EliminationReason::IllegalPlacement(_))
+            });
+            if let Some(elimination) = forfeit {
+                state.forfeit(elimination.reason);
+            } else if let Some(&start_location) = start_locations.next() {
+                on_placement(&state, start_location);
+                state.place_workers(start_location)?;
+            } else {
+                break;
+            }
+        }
+        Ok(state)
+    }
+
+    /// Replays the placements and the first `ply` actions, along with any eliminations for
+    /// illegal actions made straight after them. `ply` is capped at the number of actions.
+    pub fn replay(&self, ply: usize) -> Result<GameState, IllegalAction> {
+        self.replay_with(ply, |_, _, _| {})
+    }
+
+    pub fn game_at(&self, ply: usize) -> Result<Game, IllegalAction> {
+        Ok(*self.replay(ply)?.game())
+    }
+
+    fn replay_with(
+        &self,
+        ply: usize,
+        mut on_action: impl FnMut(&Game, usize, Action),
+    ) -> Result<GameState, IllegalAction> {
+        let mut state = self.replay_placements(|_, _| {})?;
+        let ply = ply.min(self.actions.len());
+        for turn in 0..=ply {
+            // Players running out of actions are eliminated by the state itself
+            for elimination in self.eliminations.iter() {
+                if elimination.turn == turn
+                    && matches!(elimination.reason, EliminationReason::IllegalAction(_))
+                {
+                    state.forfeit(elimination.reason);
+                }
+            }
+            if turn < ply {
+                let (player_id, action) = self.actions[turn];
+                on_action(state.game(), player_id, action);
+                state.play(action)?;
+            }
+        }
+        Ok(state)
+    }
+
+    /// Every action along with the position it was played in, and whether its player won or was
+    /// on the winning team
+    pub fn training_data(&self) -> Result<Vec<genetic_ai::TrainingData>, IllegalAction> {
+        let mut training_data = Vec::new();
+        self.replay_with(self.actions.len(), |game, player_id, action| {
+            training_data.push((self.has_won(player_id), player_id, *game, action));
+        })?;
+        Ok(training_data)
+    }
+
+    /// Every placement with the position and placements before it, and whether its player won
+    pub fn start_location_training_data(
+        &self,
+    ) -> Result<Vec<(bool, Game, Vec<StartLocation>)>, IllegalAction> {
+        let mut training_data = Vec::new();
+        self.replay_placements(|state, _| {
+            training_data.push((
+                self.has_won(state.current_player()),
+                *state.game(),
+                state.start_locations().to_vec(),
+            ));
+        })?;
+        Ok(training_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_main_loop_games() {
+        let player = random_choice_player::RandomChoice::new();
+        for _ in 0..20 {
+            let record = main_loop(
+                [Some(&player), Some(&player), Some(&player), None],
+                [Some(God::Apollo), None, Some(God::Minotaur), None],
+                &mut [],
+            );
+            assert_eq!(
+                record.players[0].as_ref().unwrap().name,
+                "RandomChoice".to_string()
+            );
+            let loaded: GameRecord =
+                serde_json::from_str(&serde_json::to_string(&record).unwrap()).unwrap();
+            assert_eq!(loaded, record);
+            let state = loaded.replay(usize::MAX).unwrap();
+            assert_eq!(state.result(), record.winner);
+            assert_eq!(state.eliminations(), &record.eliminations[..]);
+
+            let training_data = record.training_data().unwrap();
+            assert_eq!(training_data.len(), record.actions.len());
+            for (ply, &(_, player_id, game, action)) in training_data.iter().enumerate() {
+                assert_eq!(record.game_at(ply).unwrap(), game);
+                assert_eq!(record.actions[ply], (player_id, action));
+            }
+            assert_eq!(record.start_location_training_data().unwrap().len(), 3);
+        }
+    }
+
+    #[test]
+    fn seeded_games_play_out_the_same_way() {
+        let player = random_choice_player::RandomChoice::new();
+        let players = [Some(&player as &dyn Player), Some(&player), None, None];
+        let record = main_loop(players, [None; MAX_PLAYERS], &mut []);
+        let seed = record.seed.unwrap();
+        let text = replay::to_replay_string(&record).unwrap();
+        assert!(text.contains(&format!("Seed: {}\n", seed)));
+        let again = play_game(record.initial_state(), players, &mut []);
+        assert_eq!(again.seed, Some(seed));
+        assert_eq!(again.start_locations, record.start_locations);
+        assert_eq!(again.actions, record.actions);
+    }
+
+    #[test]
+    fn team_games_are_won_by_both_teammates() {
+        let player = random_choice_player::RandomChoice::new();
+        for _ in 0..10 {
+            let mut state = GameState::new(Status::seats(4), [None; MAX_PLAYERS]);
+            state.play_in_teams();
+            let record = play_game(state, [Some(&player); MAX_PLAYERS], &mut []);
+            assert!(record.teams);
+            let winner = record.winner.unwrap();
+            for (success, player_id, _, _) in record.training_data().unwrap() {
+                assert_eq!(success, player_id % 2 == winner % 2);
+            }
+            let text = replay::to_replay_string(&record).unwrap();
+            assert!(text.contains("Teams: on"));
+            assert_eq!(replay::parse_replay(&text), Ok(record));
+        }
+    }
+
+    #[test]
+    fn replays_forfeits_after_illegal_actions() {
+        let mut state = GameState::new(Status::seats(3), [None; MAX_PLAYERS]);
+        for &start in [((0, 0), (0, 1)), ((2, 2), (2, 3)), ((4, 4), (4, 3))].iter() {
+            state.place_workers(start).unwrap();
+        }
+        state
+            .play(Action::new(Worker::One, (1, 0), (0, 0)))
+            .unwrap();
+        state.forfeit(EliminationReason::IllegalAction(IllegalAction::NotAdjacent));
+        state
+            .play(Action::new(Worker::One, (3, 4), (4, 4)))
+            .unwrap();
+        let info = || {
+            Some(PlayerInfo {
+                name: "Test".to_string(),
+                kind: "Test".to_string(),
+            })
+        };
+        let record = GameRecord::new(&state, [info(), info(), info(), None]);
+        assert_eq!(record.game_at(1).unwrap().player_statuses[1], Status::Dead);
+        assert_eq!(record.game_at(2).unwrap(), *state.game());
+    }
+}
@@ -0,0 +1,486 @@
+//! A text format for saved games: a header of `Key: value` lines, then one line per action in
+//! the order they were played, e.g.
+//!
+//! ```text
+//! Santorini replay
+//! Date: 1700000000
+//! Seed: 42
+//! Player 0: GeneticAI | rust_santorini::genetic_ai::GeneticAI | Apollo
+//! Player 1: Human | main::RealPlayer | -
+//! Pieces: limited
+//! Winner: 1
+//! Start: A1,B2
+//! Start: D4,E5
+//!
+//! 0 O:A1-A2^A3
+//! 1 T:E5-D5^C5
+//! ```
+//!
+//! A player eliminated for an illegal action gets a line like `0 forfeit NotAdjacent` instead,
+//! and one who never placed their workers gets `Start: forfeit Occupied`. The `Pieces` line is
+//! only there for games played with a limited supply of building pieces, a `Teams: on` line
+//! for games played in teams, and a line like `Board: 4x3` for games on a board smaller than
+//! 5x5, giving its width and height. The `Seed` line has the seed the players' random choices
+//! were made with, see `play_game`.
+//! Loading a replay plays every action again, so a corrupt file is caught.
+
+use crate::notation::NotationError;
+use crate::notation::{action_to_string, parse_start_location, start_location_to_string};
+use crate::record::PlayerInfo;
+use crate::*;
+
+const TITLE: &str = "Santorini replay";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ReplayError {
+    /// Line numbers count from 1
+    Header {
+        line: usize,
+        text: String,
+    },
+    Notation {
+        line: usize,
+        error: NotationError,
+    },
+    IllegalAction {
+        line: usize,
+        error: IllegalAction,
+    },
+    IllegalPlacement {
+        line: usize,
+        error: IllegalPlacement,
+    },
+    WrongPlayer {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The worker isn't on the square the action says it moves from
+    MovedFrom {
+        line: usize,
+        square: String,
+    },
+    /// The winner in the header isn't who won when the game was played again
+    Winner {
+        expected: Option<usize>,
+        found: Option<usize>,
+    },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReplayError::Header { line, text } => {
+                write!(f, "line {}: unexpected {:?}", line, text)
+            }
+            ReplayError::Notation { line, error } => write!(f, "line {}: {}", line, error),
+            ReplayError::IllegalAction { line, error } => {
+                write!(f, "line {}: illegal action, {}", line, error)
+            }
+            ReplayError::IllegalPlacement { line, error } => {
+                write!(f, "line {}: illegal placement, {}", line, error)
+            }
+            ReplayError::WrongPlayer {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: player {} is to move, not player {}",
+                line, expected, found
+            ),
+            ReplayError::MovedFrom { line, square } => {
+                write!(f, "line {}: the worker isn't on {}", line, square)
+            }
+            ReplayError::Winner { expected, found } => write!(
+                f,
+                "the replay says the winner is {:?}, but playing it gives {:?}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+fn player_or_dash(player: Option<usize>) -> String {
+    player.map_or_else(|| "-".to_string(), |player| player.to_string())
+}
+
+pub fn to_replay_string(record: &GameRecord) -> Result<String, IllegalAction> {
+    let mut result = format!("{}\nDate: {}\n", TITLE, record.date);
+    if let Some(seed) = record.seed {
+        result.push_str(&format!("Seed: {}\n", seed));
+    }
+    for (player_id, player) in record.players.iter().enumerate() {
+        if let Some(player) = player {
+            let god = record.player_gods[player_id];
+            result.push_str(&format!(
+                "Player {}: {} | {} | {}\n",
+                player_id,
+                player.name,
+                player.kind,
+                god.map_or_else(|| "-".to_string(), |god| god.to_string())
+            ));
+        }
+    }
+    if record.limited_pieces {
+        result.push_str("Pieces: limited\n");
+    }
+    if record.teams {
+        result.push_str("Teams: on\n");
+    }
+    if record.board_size != BoardSize::FULL {
+        result.push_str(&format!("Board: {}\n", record.board_size));
+    }
+    result.push_str(&format!("Winner: {}\n", player_or_dash(record.winner)));
+    // Placement goes round the players in seat order
+    let mut start_locations = record.start_locations.iter();
+    for player_id in (0..MAX_PLAYERS).filter(|&i| record.players[i].is_some()) {
+        let forfeit = record
+            .eliminations
+            .iter()
+            .find_map(|elimination| match elimination.reason {
+                EliminationReason::IllegalPlacement(reason)
+                    if elimination.player_id == player_id =>
+                {
+                    Some(reason)
+                }
+                _ => None,
+            });
+        if let Some(reason) = forfeit {
+            result.push_str(&format!("Start: forfeit {:?}\n", reason));
+        } else if let Some(&start_location) = start_locations.next() {
+            result.push_str(&format!(
+                "Start: {}\n",
+                start_location_to_string(start_location)
+            ));
+        }
+    }
+    result.push('\n');
+
+    let actions = record.training_data()?;
+    for turn in 0..=actions.len() {
+        for elimination in record.eliminations.iter().filter(|e| e.turn == turn) {
+            if let EliminationReason::IllegalAction(reason) = elimination.reason {
+                result.push_str(&format!("{} forfeit {:?}\n", elimination.player_id, reason));
+            }
+        }
+        if let Some(&(_, player_id, game, action)) = actions.get(turn) {
+            result.push_str(&format!(
+                "{} {}\n",
+                player_id,
+                action_to_string(&game, player_id, action)
+            ));
+        }
+    }
+    Ok(result)
+}
+
+pub fn parse_replay(text: &str) -> Result<GameRecord, ReplayError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    let header_error = |line: usize, text: &str| ReplayError::Header {
+        line,
+        text: text.to_string(),
+    };
+    match lines.next() {
+        Some((_, TITLE)) => {}
+        Some((line, text)) => return Err(header_error(line, text)),
+        None => return Err(header_error(1, "")),
+    }
+
+    let mut players: [Option<PlayerInfo>; MAX_PLAYERS] = Default::default();
+    let mut player_gods = [None; MAX_PLAYERS];
+    let mut date = 0;
+    let mut seed = None;
+    let mut limited_pieces = false;
+    let mut teams = false;
+    let mut board_size = BoardSize::FULL;
+    let mut winner = None;
+    let mut start_locations = Vec::new();
+    for (line, text) in &mut lines {
+        if text.is_empty() {
+            break;
+        }
+        let (key, value) = match text.find(':') {
+            Some(colon) => (&text[..colon], text[colon + 1..].trim()),
+            None => return Err(header_error(line, text)),
+        };
+        match key {
+            "Date" => date = value.parse().map_err(|_| header_error(line, text))?,
+            "Seed" => seed = Some(value.parse().map_err(|_| header_error(line, text))?),
+            "Pieces" if value == "limited" => limited_pieces = true,
+            "Teams" if value == "on" => teams = true,
+            "Board" => {
+                board_size = value
+                    .split_once('x')
+                    .and_then(|(width, height)| {
+                        BoardSize::new(width.parse().ok()?, height.parse().ok()?)
+                    })
+                    .ok_or_else(|| header_error(line, text))?
+            }
+            "Winner" if value == "-" => winner = None,
+            "Winner" => winner = Some(value.parse().map_err(|_| header_error(line, text))?),
+            "Start" if value.starts_with("forfeit ") => {
+                let reason = IllegalPlacement::ALL
+                    .iter()
+                    .find(|&&error| format!("{:?}", error) == value["forfeit ".len()..])
+                    .ok_or_else(|| header_error(line, text))?;
+                start_locations.push((line, Err(*reason)));
+            }
+            "Start" => start_locations.push((
+                line,
+                Ok(parse_start_location(value)
+                    .map_err(|error| ReplayError::Notation { line, error })?),
+            )),
+            _ if key.starts_with("Player ") => {
+                let player_id = match key["Player ".len()..].parse::<usize>() {
+                    Ok(player_id) if player_id < MAX_PLAYERS => player_id,
+                    _ => return Err(header_error(line, text)),
+                };
+                // Split from the right, since only the name can contain '|'
+                let fields: Vec<&str> = value.rsplitn(3, '|').map(str::trim).collect();
+                if fields.len() != 3 {
+                    return Err(header_error(line, text));
+                }
+                let (name, kind, god) = (fields[2], fields[1], fields[0]);
+                players[player_id] = Some(PlayerInfo {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                });
+                if god != "-" {
+                    player_gods[player_id] = Some(
+                        *God::ALL
+                            .iter()
+                            .find(|found| found.to_string() == god)
+                            .ok_or_else(|| header_error(line, text))?,
+                    );
+                }
+            }
+            _ => return Err(header_error(line, text)),
+        }
+    }
+
+    let mut player_statuses = [Status::Dead; MAX_PLAYERS];
+    for (status, player) in player_statuses.iter_mut().zip(players.iter()) {
+        if player.is_some() {
+            *status = Status::Playing;
+        }
+    }
+    let mut state = GameState::new(player_statuses, player_gods);
+    if limited_pieces {
+        state.limit_pieces();
+    }
+    if teams {
+        state.play_in_teams();
+    }
+    state.set_board_size(board_size);
+    for &(line, start_location) in start_locations.iter() {
+        match start_location {
+            Ok(start_location) => state
+                .place_workers(start_location)
+                .map_err(|error| ReplayError::IllegalPlacement { line, error })?,
+            Err(_) if state.phase() != Phase::Placement => {
+                return Err(ReplayError::IllegalPlacement {
+                    line,
+                    error: IllegalPlacement::NotPlacing,
+                })
+            }
+            Err(reason) => state.forfeit(EliminationReason::IllegalPlacement(reason)),
+        }
+    }
+    for (line, text) in lines {
+        if text.is_empty() {
+            continue;
+        }
+        let mut words = text.split_whitespace();
+        let player_id = words
+            .next()
+            .and_then(|word| word.parse::<usize>().ok())
+            .ok_or_else(|| header_error(line, text))?;
+        if player_id != state.current_player() {
+            return Err(ReplayError::WrongPlayer {
+                line,
+                expected: state.current_player(),
+                found: player_id,
+            });
+        }
+        match (words.next(), words.next(), words.next()) {
+            (Some("forfeit"), Some(reason), None) => {
+                let reason = IllegalAction::ALL
+                    .iter()
+                    .find(|&&error| format!("{:?}", error) == reason)
+                    .ok_or_else(|| header_error(line, text))?;
+                state.forfeit(EliminationReason::IllegalAction(*reason));
+            }
+            (Some(action_text), None, None) => {
+                let action: Action = action_text
+                    .parse()
+                    .map_err(|error| ReplayError::Notation { line, error })?;
+                let moved_from = action_text
+                    .split_once('-')
+                    .and_then(|(start, _)| start.split_once(':'));
+                if let Some((_, square)) = moved_from {
+                    let location = state.game().worker_location(player_id, action.worker);
+                    if notation::parse_square(square) != Ok(location) {
+                        return Err(ReplayError::MovedFrom {
+                            line,
+                            square: square.to_string(),
+                        });
+                    }
+                }
+                state
+                    .play(action)
+                    .map_err(|error| ReplayError::IllegalAction { line, error })?;
+            }
+            _ => return Err(header_error(line, text)),
+        }
+    }
+    if state.result() != winner {
+        return Err(ReplayError::Winner {
+            expected: winner,
+            found: state.result(),
+        });
+    }
+
+    let mut record = GameRecord::new(&state, players);
+    record.date = date;
+    record.seed = seed;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_round_trip() {
+        let player = random_choice_player::RandomChoice::new();
+        for _ in 0..20 {
+            let record = main_loop(
+                [Some(&player), Some(&player), None, None],
+                [Some(God::Artemis), Some(God::Atlas), None, None],
+                &mut [],
+            );
+            let text = to_replay_string(&record).unwrap();
+            assert_eq!(parse_replay(&text), Ok(record));
+
+            let mut state = GameState::new(Status::seats(3), [None; MAX_PLAYERS]);
+            state.limit_pieces();
+            let mut session =
+                GameSession::new(state, [Some(&player), Some(&player), Some(&player), None]);
+            session.run();
+            let info = || Some(record::PlayerInfo::new(&player));
+            let record = GameRecord::new(session.state(), [info(), info(), info(), None]);
+            let text = to_replay_string(&record).unwrap();
+            assert!(text.contains("Pieces: limited"));
+            assert_eq!(parse_replay(&text), Ok(record));
+        }
+    }
+
+    #[test]
+    fn names_with_bars_round_trip() {
+        let player = random_choice_player::RandomChoice::new();
+        let mut record = main_loop(
+            [Some(&player), Some(&player), None, None],
+            [Some(God::Pan), None, None, None],
+            &mut [],
+        );
+        record.players[0].as_mut().unwrap().name = "left | right".to_string();
+        let text = to_replay_string(&record).unwrap();
+        assert!(text.contains("Player 0: left | right | "));
+        assert_eq!(parse_replay(&text), Ok(record));
+    }
+
+    #[test]
+    fn small_board_games_round_trip() {
+        let player = random_choice_player::RandomChoice::new();
+        let size = BoardSize::new(3, 4).unwrap();
+        for _ in 0..10 {
+            let mut state = GameState::new(Status::seats(2), [None; MAX_PLAYERS]);
+            state.set_board_size(size);
+            let record = play_game(state, [Some(&player), Some(&player), None, None], &mut []);
+            assert!(record
+                .start_locations
+                .iter()
+                .all(|&(w1, w2)| size.contains(w1) && size.contains(w2)));
+            assert!(record
+                .actions
+                .iter()
+                .all(|(_, action)| size.contains(action.movement) && size.contains(action.build)));
+            let text = to_replay_string(&record).unwrap();
+            assert!(text.contains("Board: 3x4"));
+            assert_eq!(parse_replay(&text), Ok(record));
+        }
+    }
+
+    #[test]
+    fn corrupt_replays_are_caught() {
+        let text = "Santorini replay
+Date: 0
+Player 0: A | A | -
+Player 1: B | B | -
+Winner: -
+Start: A1,B2
+Start: D4,E5
+
+0 O:A1-A2^A3
+1 T:E5-E4^E3
+0 O:A2-C4^C5
+";
+        assert_eq!(
+            parse_replay(text),
+            Err(ReplayError::IllegalAction {
+                line: 11,
+                error: IllegalAction::NotAdjacent
+            })
+        );
+        assert_eq!(
+            parse_replay(&text.replace("0 O:A2", "1 O:A2")),
+            Err(ReplayError::WrongPlayer {
+                line: 11,
+                expected: 0,
+                found: 1
+            })
+        );
+        assert_eq!(
+            parse_replay(&text.replace("0 O:A2-C4^C5\n", "0 forfeit ClimbTooHigh\n")),
+            Err(ReplayError::Winner {
+                expected: None,
+                found: Some(1)
+            })
+        );
+        assert_eq!(
+            parse_replay(&text.replace("0 O:A1-A2^A3", "0 o:a1-a2^a3")),
+            parse_replay(text)
+        );
+        assert_eq!(
+            parse_replay(&text.replace("0 O:A1-A2", "0 O:B1-A2")),
+            Err(ReplayError::MovedFrom {
+                line: 9,
+                square: "B1".to_string()
+            })
+        );
+        assert!(parse_replay(&text.replace("Date", "Time")).is_err());
+        assert_eq!(
+            parse_replay(&text.replace("Start: D4,E5", "Start: A1,E5")),
+            Err(ReplayError::IllegalPlacement {
+                line: 7,
+                error: IllegalPlacement::Occupied
+            })
+        );
+        let forfeited = "Santorini replay
+Date: 0
+Player 0: A | A | -
+Player 1: B | B | -
+Winner: 1
+Start: forfeit SameSquare
+
+";
+        let record = parse_replay(forfeited).unwrap();
+        assert_eq!(to_replay_string(&record).unwrap(), forfeited);
+    }
+}
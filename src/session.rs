@@ -0,0 +1,319 @@
+use crate::*;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Event {
+    /// The player has to place their workers or act next
+    TurnStarted {
+        player_id: usize,
+        phase: Phase,
+    },
+    WorkersPlaced {
+        player_id: usize,
+        start_location: StartLocation,
+    },
+    PlacementRejected {
+        player_id: usize,
+        start_location: StartLocation,
+        reason: IllegalPlacement,
+    },
+    ActionApplied {
+        player_id: usize,
+        action: Action,
+    },
+    ActionRejected {
+        player_id: usize,
+        action: Action,
+        reason: IllegalAction,
+    },
+    PlayerEliminated(game_state::Elimination),
+    GameWon {
+        player_id: usize,
+    },
+}
+
+/// Times a `Player` may choose an illegal placement before forfeiting the game
+const PLACEMENT_ATTEMPTS: usize = 10;
+
+/// Runs a game one placement or action at a time. Seats with a `Player` are asked for their
+/// moves by `step`, while the moves for the other seats are pushed in from outside.
+pub struct GameSession<'a> {
+    state: GameState,
+    players: [Option<&'a dyn Player>; MAX_PLAYERS],
+    pending: Vec<Event>,
+    rejected_placements: usize,
+}
+
+impl<'a> GameSession<'a> {
+    pub fn new(state: GameState, players: [Option<&'a dyn Player>; MAX_PLAYERS]) -> Self {
+        let mut pending = Vec::new();
+        if state.phase() != Phase::Finished {
+            pending.push(Event::TurnStarted {
+                player_id: state.current_player(),
+                phase: state.phase(),
+            });
+        }
+        Self {
+            state,
+            players,
+            pending,
+            rejected_placements: 0,
+        }
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.phase() == Phase::Finished
+    }
+
+    /// The player whose placement or action has to be pushed before the game can go on
+    pub fn awaiting_input(&self) -> Option<usize> {
+        let player_id = self.state.current_player();
+        if !self.is_finished() && self.players[player_id].is_none() {
+            Some(player_id)
+        } else {
+            None
+        }
+    }
+
+    /// Asks the current player's `Player` for their placement or action and applies it. A
+    /// rejected placement is asked for again on the next step, until too many have been
+    /// rejected, but a rejected action forfeits the game straight away. Does nothing if the
+    /// game is finished or awaiting input.
+    pub fn step(&mut self) -> Vec<Event> {
+        let player_id = self.state.current_player();
+        match (self.state.phase(), self.players[player_id]) {
+            (Phase::Placement, Some(player)) => {
+                let start_location =
+                    player.get_starting_position(self.state.game(), self.state.start_locations());
+                if let Err(reason) = self.place_workers(start_location) {
+                    self.pending.push(Event::PlacementRejected {
+                        player_id,
+                        start_location,
+                        reason,
+                    });
+                    self.rejected_placements += 1;
+                    if self.rejected_placements == PLACEMENT_ATTEMPTS {
+                        self.rejected_placements = 0;
+                        let eliminated = self.state.eliminations().len();
+                        self.state
+                            .forfeit(EliminationReason::IllegalPlacement(reason));
+                        self.report(eliminated);
+                    }
+                }
+            }
+            (Phase::Play, Some(player)) => {
+                let action = player.get_action(self.state.game(), player_id);
+                if let Err(reason) = self.play(action) {
+                    self.pending.push(Event::ActionRejected {
+                        player_id,
+                        action,
+                        reason,
+                    });
+                    let eliminated = self.state.eliminations().len();
+                    self.state.forfeit(EliminationReason::IllegalAction(reason));
+                    self.report(eliminated);
+                }
+            }
+            _ => {}
+        }
+        self.take_events()
+    }
+
+    /// Steps until the game is finished or waiting for a pushed placement or action
+    pub fn run(&mut self) -> Vec<Event> {
+        let mut events = self.take_events();
+        while !self.is_finished() && self.awaiting_input().is_none() {
+            events.extend(self.step());
+        }
+        events
+    }
+
+    /// Places the current player's workers. Unlike `step`, nothing changes if they are rejected.
+    pub fn push_placement(
+        &mut self,
+        start_location: StartLocation,
+    ) -> Result<Vec<Event>, IllegalPlacement> {
+        self.place_workers(start_location)?;
+        Ok(self.take_events())
+    }
+
+    /// Plays the current player's action. Unlike `step`, nothing changes if it is rejected.
+    pub fn push_action(&mut self, action: Action) -> Result<Vec<Event>, IllegalAction> {
+        self.play(action)?;
+        Ok(self.take_events())
+    }
+
+    fn place_workers(&mut self, start_location: StartLocation) -> Result<(), IllegalPlacement> {
+        let player_id = self.state.current_player();
+        let eliminated = self.state.eliminations().len();
+        self.state.place_workers(start_location)?;
+        self.rejected_placements = 0;
+        self.pending.push(Event::WorkersPlaced {
+            player_id,
+            start_location,
+        });
+        self.report(eliminated);
+        Ok(())
+    }
+
+    fn play(&mut self, action: Action) -> Result<(), IllegalAction> {
+        let player_id = self.state.current_player();
+        let eliminated = self.state.eliminations().len();
+        self.state.play(action)?;
+        self.pending
+            .push(Event::ActionApplied { player_id, action });
+        self.report(eliminated);
+        Ok(())
+    }
+
+    /// Queues the events following a change to the state, given how many eliminations there
+    /// were before it
+    fn report(&mut self, eliminated: usize) {
+        for &elimination in self.state.eliminations()[eliminated..].iter() {
+            self.pending.push(Event::PlayerEliminated(elimination));
+        }
+        self.pending.push(match self.state.result() {
+            Some(player_id) => Event::GameWon { player_id },
+            None => Event::TurnStarted {
+                player_id: self.state.current_player(),
+                phase: self.state.phase(),
+            },
+        });
+    }
+
+    fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushed_and_pulled_seats_take_turns() {
+        let ai = first_choice_player::FirstChoice::new();
+        let mut session = GameSession::new(
+            GameState::new(Status::seats(2), [None; MAX_PLAYERS]),
+            [None, Some(&ai), None, None],
+        );
+        assert_eq!(session.awaiting_input(), Some(0));
+        assert_eq!(
+            session.step(),
+            vec![Event::TurnStarted {
+                player_id: 0,
+                phase: Phase::Placement
+            }]
+        );
+        assert_eq!(
+            session.push_placement(((4, 4), (4, 4))),
+            Err(IllegalPlacement::SameSquare)
+        );
+        let events = session.push_placement(((4, 4), (4, 3))).unwrap();
+        assert_eq!(
+            events[1],
+            Event::TurnStarted {
+                player_id: 1,
+                phase: Phase::Placement
+            }
+        );
+        let events = session.run();
+        assert!(matches!(
+            events[0],
+            Event::WorkersPlaced { player_id: 1, .. }
+        ));
+        assert_eq!(
+            events[1],
+            Event::TurnStarted {
+                player_id: 0,
+                phase: Phase::Play
+            }
+        );
+        assert_eq!(session.awaiting_input(), Some(0));
+        assert_eq!(
+            session.push_action(Action::new(Worker::One, (2, 2), (2, 3))),
+            Err(IllegalAction::NotAdjacent)
+        );
+        let action = Action::new(Worker::One, (3, 4), (2, 4));
+        let events = session.push_action(action).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::ActionApplied {
+                    player_id: 0,
+                    action
+                },
+                Event::TurnStarted {
+                    player_id: 1,
+                    phase: Phase::Play
+                }
+            ]
+        );
+        let events = session.step();
+        assert!(matches!(
+            events[0],
+            Event::ActionApplied { player_id: 1, .. }
+        ));
+        assert_eq!(session.state().turn(), 2);
+    }
+
+    #[test]
+    fn sessions_between_players_run_to_a_winner() {
+        let ai = random_choice_player::RandomChoice::new();
+        for _ in 0..20 {
+            let mut session = GameSession::new(
+                GameState::new(Status::seats(3), [None; MAX_PLAYERS]),
+                [Some(&ai), Some(&ai), Some(&ai), None],
+            );
+            let events = session.run();
+            let winner = session.state().result().unwrap();
+            assert_eq!(events.last(), Some(&Event::GameWon { player_id: winner }));
+            assert_eq!(
+                events
+                    .iter()
+                    .filter(|event| matches!(event, Event::ActionApplied { .. }))
+                    .count(),
+                session.state().turn()
+            );
+        }
+    }
+
+    struct CornerPlacer;
+
+    impl Player for CornerPlacer {
+        fn get_action(&self, game: &Game, player_id: usize) -> Action {
+            game.possible_actions(player_id).next().unwrap()
+        }
+
+        fn get_starting_position(&self, _: &Game, _: &[StartLocation]) -> StartLocation {
+            ((0, 0), (4, 4))
+        }
+    }
+
+    #[test]
+    fn players_forfeit_after_repeated_illegal_placements() {
+        let ai = CornerPlacer;
+        let mut session = GameSession::new(
+            GameState::new(Status::seats(3), [None; MAX_PLAYERS]),
+            [Some(&ai), Some(&ai), Some(&ai), None],
+        );
+        let events = session.run();
+        let rejections = events
+            .iter()
+            .filter(|event| matches!(event, Event::PlacementRejected { .. }))
+            .count();
+        assert_eq!(rejections, 2 * PLACEMENT_ATTEMPTS);
+        assert_eq!(
+            session.state().eliminations()[1].reason,
+            EliminationReason::IllegalPlacement(IllegalPlacement::Occupied)
+        );
+        assert_eq!(session.state().result(), Some(0));
+
+        let info = || Some(record::PlayerInfo::new(&ai));
+        let record = GameRecord::new(session.state(), [info(), info(), info(), None]);
+        assert_eq!(record.replay(0).unwrap().result(), Some(0));
+    }
+}
@@ -0,0 +1,279 @@
+//! The board looks the same after any of its 8 rotations and reflections, so positions that
+//! only differ by one of them play out the same way. A board that isn't square only keeps 4 of
+//! them, see `Symmetry::fits`.
+
+use crate::*;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum Symmetry {
+    Identity,
+    /// A quarter turn, undone by `Rotate270`
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Swaps the top and bottom rows
+    FlipRows,
+    /// Swaps the left and right columns
+    FlipColumns,
+    /// Reflects in the diagonal through A1
+    Transpose,
+    /// Reflects in the diagonal through E1
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipRows,
+        Symmetry::FlipColumns,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// Whether a board of the given size looks the same after the symmetry. Quarter turns and
+    /// diagonal reflections only keep a square board the same.
+    pub fn fits(self, size: BoardSize) -> bool {
+        size.width == size.height
+            || matches!(
+                self,
+                Symmetry::Identity
+                    | Symmetry::Rotate180
+                    | Symmetry::FlipRows
+                    | Symmetry::FlipColumns
+            )
+    }
+
+    /// Where a square of a board of the given size ends up. Squares off the board, like the
+    /// workers of eliminated players, stay where they are.
+    pub fn apply(self, (x, y): (u8, u8), size: BoardSize) -> (u8, u8) {
+        if !size.contains((x, y)) {
+            return (x, y);
+        }
+        let (last_x, last_y) = (size.height - 1, size.width - 1);
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (y, last_x - x),
+            Symmetry::Rotate180 => (last_x - x, last_y - y),
+            Symmetry::Rotate270 => (last_y - y, x),
+            Symmetry::FlipRows => (last_x - x, y),
+            Symmetry::FlipColumns => (x, last_y - y),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (last_y - y, last_x - x),
+        }
+    }
+
+    /// The symmetry that undoes this one
+    pub fn inverse(self) -> Self {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            symmetry => symmetry,
+        }
+    }
+}
+
+impl Action {
+    /// The action transformed along with a game on a board of the given size
+    pub fn transform(self, symmetry: Symmetry, size: BoardSize) -> Self {
+        Self {
+            movement: symmetry.apply(self.movement, size),
+            build: symmetry.apply(self.build, size),
+            extra_build: self.extra_build.map(|square| symmetry.apply(square, size)),
+            ..self
+        }
+    }
+}
+
+impl Game {
+    /// The game with the board turned or reflected, or `None` if the symmetry doesn't fit its size
+    pub fn transform(&self, symmetry: Symmetry) -> Option<Self> {
+        if !symmetry.fits(self.size) {
+            return None;
+        }
+        let mut result = *self;
+        for (x, y) in self.size.squares() {
+            let (new_x, new_y) = symmetry.apply((x, y), self.size);
+            result.board[new_x as usize][new_y as usize] = self.board[x as usize][y as usize];
+        }
+        for (new, &(first, second)) in result
+            .player_locations
+            .iter_mut()
+            .zip(self.player_locations.iter())
+        {
+            *new = (
+                symmetry.apply(first, self.size),
+                symmetry.apply(second, self.size),
+            );
+        }
+        result.refresh_hash();
+        Some(result)
+    }
+
+    /// The smallest of the transforms of the game that fit its board, comparing the board row by
+    /// row and then the worker squares, along with the symmetry that gives it. Games that are
+    /// transforms of each other have the same canonical form, and an action chosen in the
+    /// canonical game is turned back with `action.transform(symmetry.inverse(), game.size)`.
+    pub fn canonical(&self) -> (Self, Symmetry) {
+        Symmetry::ALL
+            .iter()
+            .filter_map(|&symmetry| Some((self.transform(symmetry)?, symmetry)))
+            .min_by_key(|(game, _)| {
+                let mut levels = [[0; 5]; 5];
+                for (row, board_row) in levels.iter_mut().zip(game.board.iter()) {
+                    for (level, tower) in row.iter_mut().zip(board_row.iter()) {
+                        *level = tower.to_int();
+                    }
+                }
+                (levels, game.player_locations)
+            })
+            .unwrap()
+    }
+}
+
+/// Each entry along with its rotated and reflected copies, for the symmetries that fit the
+/// board of the game in it. Copies that are the same as an entry already kept, because the
+/// position is symmetric or was recorded more than once, are dropped.
+fn augment<T: Copy + Eq + std::hash::Hash>(
+    entries: &[T],
+    transform: impl Fn(T, Symmetry) -> Option<T>,
+) -> Vec<T> {
+    let mut seen = std::collections::HashSet::new();
+    let mut result = Vec::new();
+    for &entry in entries.iter() {
+        for &symmetry in Symmetry::ALL.iter() {
+            let transformed = match transform(entry, symmetry) {
+                Some(transformed) => transformed,
+                None => continue,
+            };
+            if seen.insert(transformed) {
+                result.push(transformed);
+            }
+        }
+    }
+    result
+}
+
+/// Up to 8 copies of every action, see `GeneticAI::learn`
+pub fn augment_training_data(
+    training_data: &[genetic_ai::TrainingData],
+) -> Vec<genetic_ai::TrainingData> {
+    augment(
+        training_data,
+        |(success, player_id, game, action), symmetry| {
+            Some((
+                success,
+                player_id,
+                game.transform(symmetry)?,
+                action.transform(symmetry, game.size),
+            ))
+        },
+    )
+}
+
+/// Up to 8 copies of every position, see `GamePrediction::learn`
+pub fn augment_games(games: &[(Game, usize, bool)]) -> Vec<(Game, usize, bool)> {
+    augment(games, |(game, player_id, success), symmetry| {
+        Some((game.transform(symmetry)?, player_id, success))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_game() -> Game {
+        Game::from_position_string(
+            "01200/00300/00040/10000/00002 A1,B2/C3,D5/E2,E1 PPP 1 Apollo,-,-",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn transforms_are_undone_by_their_inverse() {
+        let game = example_game();
+        for &symmetry in Symmetry::ALL.iter() {
+            let transformed = game.transform(symmetry).unwrap();
+            assert_eq!(transformed.transform(symmetry.inverse()), Some(game));
+            assert_eq!(transformed.to_move, 1);
+        }
+        let images: std::collections::HashSet<Game> = Symmetry::ALL
+            .iter()
+            .map(|&symmetry| game.transform(symmetry).unwrap())
+            .collect();
+        assert_eq!(images.len(), 8);
+    }
+
+    #[test]
+    fn transformed_games_have_transformed_actions() {
+        let game = example_game();
+        for &symmetry in Symmetry::ALL.iter() {
+            let transformed = game.transform(symmetry).unwrap();
+            let mut expected: Vec<Action> = game
+                .list_possible_actions(1)
+                .into_iter()
+                .map(|action| action.transform(symmetry, game.size))
+                .collect();
+            let mut actions = transformed.list_possible_actions(1);
+            let key = |action: &Action| (action.worker as u8, action.movement, action.build);
+            expected.sort_by_key(key);
+            actions.sort_by_key(key);
+            assert_eq!(actions, expected);
+        }
+    }
+
+    #[test]
+    fn augmenting_drops_duplicates() {
+        let game = example_game();
+        let action = game.list_possible_actions(1)[0];
+        let entry = (true, 1, game, action);
+        let augmented = augment_training_data(&[entry, entry]);
+        assert_eq!(augmented.len(), 8);
+        assert_eq!(augmented[0], entry);
+
+        // Workers in opposite corners are only left where they are by the reflection in the
+        // diagonal through them, so half of the 8 orientations are copies
+        let game = Game::from_position_string("00000/00000/00000/00000/00000 A1,E5/-,-/-,- PDD 0")
+            .unwrap();
+        assert_eq!(augment_games(&[(game, 0, true)]).len(), 4);
+    }
+
+    #[test]
+    fn transforms_share_a_canonical_form() {
+        let game = example_game();
+        let (canonical, symmetry) = game.canonical();
+        assert_eq!(game.transform(symmetry), Some(canonical));
+        for &other in Symmetry::ALL.iter() {
+            assert_eq!(game.transform(other).unwrap().canonical().0, canonical);
+        }
+    }
+
+    #[test]
+    fn boards_that_are_not_square_only_turn_halfway() {
+        let game = Game::from_position_string("000/010/002/000 A1,C3/B2,C4/-,- PPD 1").unwrap();
+        let fitting: Vec<Symmetry> = Symmetry::ALL
+            .iter()
+            .copied()
+            .filter(|symmetry| symmetry.fits(game.size))
+            .collect();
+        assert_eq!(fitting.len(), 4);
+        for &symmetry in Symmetry::ALL.iter() {
+            match game.transform(symmetry) {
+                Some(transformed) => {
+                    assert!(fitting.contains(&symmetry));
+                    assert_eq!(transformed.transform(symmetry.inverse()), Some(game));
+                    assert_eq!(transformed.canonical().0, game.canonical().0);
+                }
+                None => assert!(!fitting.contains(&symmetry)),
+            }
+        }
+        assert_eq!(
+            game.transform(Symmetry::Rotate180)
+                .unwrap()
+                .to_position_string(),
+            "000/200/010/000 C4,A2/B3,A1/-,- PPD 1 -,-,-"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Round-robin tournaments, for telling whether one player is stronger than another. Every pair
+//! of players meets the same number of times in both seat orders, with the games played in
+//! parallel, and the results are summed up as a crosstable, win rates with confidence intervals
+//! and Elo ratings.
+
+use crate::*;
+use rayon::prelude::*;
+
+/// Normal quantile for a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Rating the Elo ratings average out at
+pub const MEAN_ELO: f64 = 1500.0;
+
+/// Share of games won, along with a 95% Wilson score interval for it
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct WinRate {
+    pub wins: usize,
+    pub games: usize,
+    pub rate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl WinRate {
+    pub fn new(wins: usize, games: usize) -> Self {
+        if games == 0 {
+            return Self {
+                wins,
+                games,
+                rate: 0.0,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+        let n = games as f64;
+        let rate = wins as f64 / n;
+        let z2 = Z_95 * Z_95;
+        let centre = (rate + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let spread = Z_95 * (rate * (1.0 - rate) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        Self {
+            wins,
+            games,
+            rate,
+            low: (centre - spread).max(0.0),
+            high: (centre + spread).min(1.0),
+        }
+    }
+}
+
+impl std::fmt::Display for WinRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}% ({:.1}% to {:.1}%)",
+            100.0 * self.rate,
+            100.0 * self.low,
+            100.0 * self.high
+        )
+    }
+}
+
+/// What a tournament came to
+#[derive(Debug, PartialEq, Clone)]
+pub struct Standings {
+    pub names: Vec<String>,
+    /// `wins[i][j]` is how many games player `i` won against player `j`
+    pub wins: Vec<Vec<usize>>,
+}
+
+impl Standings {
+    pub fn new(names: Vec<String>) -> Self {
+        let wins = vec![vec![0; names.len()]; names.len()];
+        Self { names, wins }
+    }
+
+    /// How the player did against everyone else
+    pub fn win_rate(&self, player: usize) -> WinRate {
+        let wins = self.wins[player].iter().sum();
+        let losses: usize = self.wins.iter().map(|row| row[player]).sum();
+        WinRate::new(wins, wins + losses)
+    }
+
+    /// How `player` did against `opponent`
+    pub fn head_to_head(&self, player: usize, opponent: usize) -> WinRate {
+        let wins = self.wins[player][opponent];
+        WinRate::new(wins, wins + self.wins[opponent][player])
+    }
+
+    /// Elo ratings fitted to all of the games at once, so the order they were played in doesn't
+    /// matter, and averaging `MEAN_ELO`. Each pair of players that met is given half a win each
+    /// on top of their results, which keeps the rating of a player who never won finite.
+    pub fn elo_ratings(&self) -> Vec<f64> {
+        let players = self.names.len();
+        let played = |i: usize, j: usize| self.wins[i][j] + self.wins[j][i] > 0;
+        let wins = |i: usize, j: usize| self.wins[i][j] as f64 + 0.5;
+        // Bradley-Terry strengths by minorization-maximization, where a rating difference
+        // of 400 means 10 times the strength
+        let mut strengths = vec![1.0; players];
+        for _ in 0..1000 {
+            let mut next: Vec<f64> = (0..players)
+                .map(|i| {
+                    let (won, expected) = (0..players).filter(|&j| j != i && played(i, j)).fold(
+                        (0.0, 0.0),
+                        |(won, expected), j| {
+                            let games = wins(i, j) + wins(j, i);
+                            (
+                                won + wins(i, j),
+                                expected + games / (strengths[i] + strengths[j]),
+                            )
+                        },
+                    );
+                    if expected > 0.0 {
+                        won / expected
+                    } else {
+                        strengths[i]
+                    }
+                })
+                .collect();
+            let scale = (next.iter().map(|s: &f64| s.ln()).sum::<f64>() / players as f64).exp();
+            for strength in next.iter_mut() {
+                *strength /= scale;
+            }
+            let converged = next
+                .iter()
+                .zip(strengths.iter())
+                .all(|(a, b)| (a / b).ln().abs() < 1e-9);
+            strengths = next;
+            if converged {
+                break;
+            }
+        }
+        strengths
+            .iter()
+            .map(|strength| MEAN_ELO + 400.0 * strength.log10())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Standings {
+    /// The crosstable of wins and losses from each row player's side, then each player's win
+    /// rate and Elo rating, best first
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(6);
+        write!(f, "{:width$}", "", width = width)?;
+        for name in self.names.iter() {
+            write!(f, "  {:>width$}", name, width = width)?;
+        }
+        writeln!(f)?;
+        for (i, name) in self.names.iter().enumerate() {
+            write!(f, "{:width$}", name, width = width)?;
+            for j in 0..self.names.len() {
+                let cell = if i == j {
+                    "-".to_string()
+                } else {
+                    format!("{}-{}", self.wins[i][j], self.wins[j][i])
+                };
+                write!(f, "  {:>width$}", cell, width = width)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        let ratings = self.elo_ratings();
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| ratings[b].partial_cmp(&ratings[a]).unwrap());
+        for i in order {
+            let win_rate = self.win_rate(i);
+            writeln!(
+                f,
+                "{:width$}  Elo {:>4.0}  won {} of {}, {}",
+                self.names[i],
+                ratings[i],
+                win_rate.wins,
+                win_rate.games,
+                win_rate,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays `games_per_order` two player games between every pair of `players` with each of them
+/// seated first, running the games in parallel
+pub fn round_robin(players: &[(&str, &dyn Player)], games_per_order: usize) -> Standings {
+    let mut pairings = Vec::new();
+    for first in 0..players.len() {
+        for second in (0..players.len()).filter(|&second| second != first) {
+            for _ in 0..games_per_order {
+                pairings.push((first, second));
+            }
+        }
+    }
+    let winners: Vec<(usize, usize, Option<usize>)> = pairings
+        .into_par_iter()
+        .map(|(first, second)| {
+            let record = main_loop(
+                [Some(players[first].1), Some(players[second].1), None, None],
+                [None; MAX_PLAYERS],
+                &mut [],
+            );
+            (first, second, record.winner)
+        })
+        .collect();
+    let mut standings = Standings::new(players.iter().map(|(name, _)| name.to_string()).collect());
+    for (first, second, winner) in winners {
+        match winner {
+            Some(0) => standings.wins[first][second] += 1,
+            Some(1) => standings.wins[second][first] += 1,
+            _ => {}
+        }
+    }
+    standings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_pairing_is_played_in_both_seat_orders() {
+        let random = random_choice_player::RandomChoice::new();
+        let first_choice = first_choice_player::FirstChoice::new();
+        let players: [(&str, &dyn Player); 3] = [
+            ("Random", &random),
+            ("Other random", &random),
+            ("First choice", &first_choice),
+        ];
+        let standings = round_robin(&players, 3);
+        for i in 0..3 {
+            assert_eq!(standings.wins[i][i], 0);
+            assert_eq!(standings.win_rate(i).games, 12);
+            for j in (0..3).filter(|&j| j != i) {
+                assert_eq!(standings.head_to_head(i, j).games, 6);
+            }
+        }
+        let text = standings.to_string();
+        assert!(text.contains("Other random"));
+        assert!(text.contains("First choice"));
+    }
+
+    #[test]
+    fn win_rates_have_confidence_intervals() {
+        let even = WinRate::new(50, 100);
+        assert_eq!(even.rate, 0.5);
+        assert!((even.low - 0.404).abs() < 0.001 && (even.high - 0.596).abs() < 0.001);
+        let swept = WinRate::new(10, 10);
+        assert_eq!(swept.high, 1.0);
+        assert!(swept.low > 0.7 && swept.low < 0.75);
+        assert_eq!(WinRate::new(0, 0).to_string(), "0.0% (0.0% to 100.0%)");
+    }
+
+    #[test]
+    fn elo_ratings_follow_the_results() {
+        let mut standings = Standings::new(vec!["A".into(), "B".into(), "C".into()]);
+        standings.wins = vec![vec![0, 10, 20], vec![10, 0, 20], vec![0, 0, 0]];
+        let ratings = standings.elo_ratings();
+        assert!((ratings[0] - ratings[1]).abs() < 1e-6);
+        assert!(ratings[1] > ratings[2] + 200.0);
+        assert!((ratings.iter().sum::<f64>() / 3.0 - MEAN_ELO).abs() < 1e-6);
+
+        // A 3 to 1 record is 10^(d / 400) = 3, so a difference of about 191
+        let mut standings = Standings::new(vec!["A".into(), "B".into()]);
+        standings.wins = vec![vec![0, 2999], vec![999, 0]];
+        let ratings = standings.elo_ratings();
+        assert!((ratings[0] - ratings[1] - 400.0 * 3f64.log10()).abs() < 1.0);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::*;
+
+pub struct Keys {
+    pub levels: [[u64; 5]; 25],
+    pub workers: [[[u64; 25]; 2]; MAX_PLAYERS],
+    pub dead: [u64; MAX_PLAYERS],
+    pub to_move: [u64; MAX_PLAYERS],
+    pub gods: [[u64; 9]; MAX_PLAYERS],
+    /// Athena having moved up on her last turn
+    pub moved_up: [u64; MAX_PLAYERS],
+    /// Pieces left of each kind under the limited supply rule, up to 22
+    pub supply: [[u64; 23]; 4],
+    pub teams: u64,
+    /// A worker that has moved and is yet to build
+    pub to_build: [u64; 2],
+    /// Boards smaller than 5x5, by width and height less 3
+    pub sizes: [[u64; 3]; 3],
+}
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> Keys {
+    let mut keys = Keys {
+        levels: [[0; 5]; 25],
+        workers: [[[0; 25]; 2]; MAX_PLAYERS],
+        dead: [0; MAX_PLAYERS],
+        to_move: [0; MAX_PLAYERS],
+        gods: [[0; 9]; MAX_PLAYERS],
+        moved_up: [0; MAX_PLAYERS],
+        supply: [[0; 23]; 4],
+        teams: 0,
+        to_build: [0; 2],
+        sizes: [[0; 3]; 3],
+    };
+    let mut state = 0x5a4e_7472_6973_0001;
+    let mut square = 0;
+    while square < 25 {
+        // Empty squares hash to 0, so an empty board does too
+        let mut level = 1;
+        while level < 5 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys.levels[square][level] = key;
+            level += 1;
+        }
+        square += 1;
+    }
+    let mut player = 0;
+    while player < MAX_PLAYERS {
+        let mut worker = 0;
+        while worker < 2 {
+            let mut square = 0;
+            while square < 25 {
+                let (next, key) = splitmix64(state);
+                state = next;
+                keys.workers[player][worker][square] = key;
+                square += 1;
+            }
+            worker += 1;
+        }
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys.dead[player] = key;
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys.to_move[player] = key;
+        let mut god = 0;
+        while god < 9 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys.gods[player][god] = key;
+            god += 1;
+        }
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys.moved_up[player] = key;
+        player += 1;
+    }
+    let mut piece = 0;
+    while piece < 4 {
+        let mut left = 0;
+        while left < 23 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys.supply[piece][left] = key;
+            left += 1;
+        }
+        piece += 1;
+    }
+    let (next, key) = splitmix64(state);
+    state = next;
+    keys.teams = key;
+    let mut worker = 0;
+    while worker < 2 {
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys.to_build[worker] = key;
+        worker += 1;
+    }
+    let mut width = 0;
+    while width < 3 {
+        let mut height = 0;
+        while height < 3 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys.sizes[width][height] = key;
+            height += 1;
+        }
+        width += 1;
+    }
+    keys
+}
+
+pub static KEYS: Keys = generate_keys();
+
+pub fn level_key((x, y): (u8, u8), level: TowerStates) -> u64 {
+    KEYS.levels[x as usize * 5 + y as usize][level.to_int() as usize]
+}
+
+pub fn worker_key(player_id: usize, worker: Worker, (x, y): (u8, u8)) -> u64 {
+    if is_in_storage((x, y)) {
+        KEYS.workers[player_id][worker as usize][x as usize * 5 + y as usize]
+    } else {
+        0
+    }
+}
+
+pub fn status_key(player_id: usize, status: Status) -> u64 {
+    match status {
+        Status::Playing => 0,
+        Status::Dead => KEYS.dead[player_id],
+    }
+}
+
+pub fn god_key(player_id: usize, god: Option<God>) -> u64 {
+    god.map_or(0, |god| KEYS.gods[player_id][god as usize])
+}
+
+pub fn effects_key(effects: TurnEffects) -> u64 {
+    effects
+        .moved_up
+        .map_or(0, |player_id| KEYS.moved_up[player_id])
+        ^ effects
+            .to_build
+            .map_or(0, |worker| KEYS.to_build[worker as usize])
+}
+
+pub fn size_key(size: BoardSize) -> u64 {
+    if size == BoardSize::FULL {
+        0
+    } else {
+        KEYS.sizes[size.width as usize - 3][size.height as usize - 3]
+    }
+}
+
+pub fn supply_key(piece: usize, left: u8) -> u64 {
+    KEYS.supply[piece][(left as usize).min(22)]
+}
+
+/// Hashes the game from scratch. `Game` keeps its own hash up to date as moves are applied,
+/// so this is only needed after changing its fields directly.
+pub fn hash_game(game: &Game) -> u64 {
+    let mut hash = KEYS.to_move[game.to_move];
+    for (x, row) in game.board.iter().enumerate() {
+        for (y, &level) in row.iter().enumerate() {
+            hash ^= level_key((x as u8, y as u8), level);
+        }
+    }
+    for (player_id, &(w1, w2)) in game.player_locations.iter().enumerate() {
+        hash ^= worker_key(player_id, Worker::One, w1)
+            ^ worker_key(player_id, Worker::Two, w2)
+            ^ status_key(player_id, game.player_statuses[player_id])
+            ^ god_key(player_id, game.player_gods[player_id]);
+    }
+    if let Some(supply) = game.piece_supply {
+        for (piece, &left) in supply.iter().enumerate() {
+            hash ^= supply_key(piece, left);
+        }
+    }
+    if game.teams {
+        hash ^= KEYS.teams;
+    }
+    hash ^ effects_key(game.effects) ^ size_key(game.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    fn start_game() -> Game {
+        Game::new(
+            [[TowerStates::Empty; 5]; 5],
+            [
+                ((1, 1), (3, 3)),
+                ((1, 3), (3, 1)),
+                ((2, 2), (0, 4)),
+                ((17, 17), (17, 17)),
+            ],
+            Status::seats(3),
+        )
+    }
+
+    #[test]
+    fn incremental_hash_matches_full_hash() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for gods in [
+            [None; MAX_PLAYERS],
+            [
+                Some(God::Apollo),
+                Some(God::Minotaur),
+                Some(God::Demeter),
+                None,
+            ],
+            [Some(God::Athena), Some(God::Hera), Some(God::Pan), None],
+        ]
+        .iter()
+        {
+            for _ in 0..20 {
+                let mut game = start_game();
+                game.set_player_gods(*gods);
+                if rng.gen_bool(0.5) {
+                    game.limit_pieces();
+                }
+                loop {
+                    let player_id = game.to_move;
+                    let actions = game.list_possible_actions(player_id);
+                    match actions.choose(&mut rng) {
+                        Some(&action) => {
+                            if game.apply_action(player_id, action, true).unwrap().won {
+                                break;
+                            }
+                        }
+                        None => game.set_status(player_id, Status::Dead),
+                    }
+                    assert_eq!(game.zobrist_hash(), hash_game(&game));
+                    if game.next_player(player_id) == player_id {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transpositions_are_equal() {
+        let actions = [
+            (0, Action::new(Worker::One, (1, 0), (0, 0))),
+            (1, Action::new(Worker::One, (0, 3), (0, 2))),
+            (2, Action::new(Worker::Two, (1, 4), (2, 4))),
+            (0, Action::new(Worker::Two, (4, 3), (4, 4))),
+        ];
+        let mut first = start_game();
+        for &(player_id, action) in actions.iter() {
+            first.apply_action(player_id, action, false).unwrap();
+        }
+        let mut second = start_game();
+        for &(player_id, action) in [actions[3], actions[1], actions[2], actions[0]].iter() {
+            second.apply_action(player_id, action, false).unwrap();
+        }
+        assert_eq!(first, second);
+        let mut positions = std::collections::HashSet::new();
+        positions.insert(first);
+        assert!(positions.contains(&second));
+    }
+
+    #[test]
+    fn side_to_move_changes_hash() {
+        let mut game = start_game();
+        let hash = game.zobrist_hash();
+        game.to_move = 1;
+        game.refresh_hash();
+        assert_ne!(game.zobrist_hash(), hash);
+    }
+
+    #[test]
+    fn hash_survives_serialization() {
+        let mut game = start_game();
+        game.apply_action(0, Action::new(Worker::One, (1, 0), (0, 0)), false)
+            .unwrap();
+        let loaded: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!(loaded.zobrist_hash(), game.zobrist_hash());
+        assert_eq!(loaded, game);
+    }
+}